@@ -0,0 +1,230 @@
+use crate::config::{CODEX_BIN, DEFAULT_BACKEND, RESULT_MAX_CHARS, RESULT_SCAN_LINES};
+use crate::escalation::EscalationPolicy;
+use crate::logs;
+use crate::settings::{self, BackendSettings, Source};
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BackendError {
+    #[error("unknown backend {0}")]
+    Unknown(String),
+    #[error("backend {name}: {message}")]
+    Invalid { name: String, message: String },
+}
+
+/// 从任务日志中提取结果摘要的方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResultExtractor {
+    /// 日志最后一行非空内容
+    LastLine,
+    /// 日志末尾最后一个 JSON 对象中的指定字段
+    JsonField(String),
+}
+
+impl FromStr for ResultExtractor {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(':') {
+            None if value == "last_line" => Ok(ResultExtractor::LastLine),
+            Some(("json", field)) if !field.is_empty() => {
+                Ok(ResultExtractor::JsonField(field.to_owned()))
+            }
+            _ => Err(format!(
+                "invalid result extractor {value}, expected last_line|json:<field>"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ResultExtractor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResultExtractor::LastLine => write!(f, "last_line"),
+            ResultExtractor::JsonField(field) => write!(f, "json:{field}"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ResultExtractor {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl ResultExtractor {
+    pub fn extract(&self, log_path: &Path) -> io::Result<Option<String>> {
+        let mut file = File::open(log_path)?;
+        let offset = logs::tail_offset(&mut file, RESULT_SCAN_LINES)?;
+        let mut tail = Vec::new();
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_to_end(&mut tail)?;
+        let tail = String::from_utf8_lossy(&tail);
+        let mut lines = tail.lines().rev().map(str::trim).filter(|l| !l.is_empty());
+
+        let found = match self {
+            ResultExtractor::LastLine => lines.next().map(str::to_owned),
+            ResultExtractor::JsonField(field) => lines.find_map(|line| {
+                let value: serde_json::Value = serde_json::from_str(line).ok()?;
+                match value.get(field)? {
+                    serde_json::Value::String(text) => Some(text.clone()),
+                    other => Some(other.to_string()),
+                }
+            }),
+        };
+        Ok(found.map(|text| truncate_chars(&text, RESULT_MAX_CHARS)))
+    }
+}
+
+/// 一个可被 codex-warden 监督的命令行 agent
+#[derive(Debug, Clone)]
+pub struct Backend {
+    pub name: String,
+    pub binary: OsString,
+    pub default_args: Vec<String>,
+    pub version_args: Vec<String>,
+    pub result_extractor: Option<ResultExtractor>,
+    /// 取消、超时或 Ctrl+C 时终止任务的信号序列
+    pub stop_policy: EscalationPolicy,
+}
+
+impl Backend {
+    /// 内置配置之上叠加配置文件 `[backends.<name>]`；未知名称必须在配置文件中给出 `binary`
+    pub fn load(name: &str) -> Result<Self, BackendError> {
+        let overrides = settings::get()
+            .backends
+            .get(name)
+            .map(|setting| &setting.value);
+        let mut backend = match builtin(name) {
+            Some(backend) => backend,
+            None => {
+                let binary = overrides
+                    .and_then(|settings| settings.binary.clone())
+                    .ok_or_else(|| BackendError::Unknown(name.to_owned()))?;
+                Backend {
+                    name: name.to_owned(),
+                    binary: OsString::from(binary),
+                    default_args: Vec::new(),
+                    version_args: vec!["--version".to_owned()],
+                    result_extractor: None,
+                    stop_policy: EscalationPolicy::default(),
+                }
+            }
+        };
+        if let Some(overrides) = overrides {
+            backend.apply(overrides);
+        }
+        // 环境变量 `CODEX_WARDEN_CODEX_BIN` 始终优先于配置文件
+        let codex_bin = &settings::get().codex_bin;
+        if name == DEFAULT_BACKEND
+            && let (Some(binary), Source::Env(_)) = (&codex_bin.value, &codex_bin.source)
+        {
+            backend.binary = OsString::from(binary);
+        }
+        if backend.binary.is_empty() {
+            return Err(BackendError::Invalid {
+                name: name.to_owned(),
+                message: "binary must not be empty".to_owned(),
+            });
+        }
+        Ok(backend)
+    }
+
+    fn apply(&mut self, overrides: &BackendSettings) {
+        if let Some(binary) = &overrides.binary {
+            self.binary = OsString::from(binary);
+        }
+        if let Some(args) = &overrides.args {
+            self.default_args = args.clone();
+        }
+        if let Some(version_args) = &overrides.version_args {
+            self.version_args = version_args.clone();
+        }
+        if let Some(extractor) = &overrides.result {
+            self.result_extractor = Some(extractor.clone());
+        }
+        if let Some(policy) = &overrides.stop_policy {
+            self.stop_policy = policy.clone();
+        }
+    }
+}
+
+fn builtin(name: &str) -> Option<Backend> {
+    let backend = match name {
+        DEFAULT_BACKEND => Backend {
+            name: name.to_owned(),
+            binary: codex_binary(),
+            default_args: Vec::new(),
+            version_args: vec!["--version".to_owned()],
+            result_extractor: None,
+            stop_policy: EscalationPolicy::default(),
+        },
+        "claude" => Backend {
+            name: name.to_owned(),
+            binary: OsString::from("claude"),
+            default_args: Vec::new(),
+            version_args: vec!["--version".to_owned()],
+            result_extractor: Some(ResultExtractor::JsonField("result".to_owned())),
+            stop_policy: EscalationPolicy::default(),
+        },
+        "gemini" => Backend {
+            name: name.to_owned(),
+            binary: OsString::from("gemini"),
+            default_args: Vec::new(),
+            version_args: vec!["--version".to_owned()],
+            result_extractor: None,
+            stop_policy: EscalationPolicy::default(),
+        },
+        _ => return None,
+    };
+    Some(backend)
+}
+
+/// 顶层 `codex_bin` 配置键（含环境变量覆盖）
+fn codex_binary() -> OsString {
+    settings::get()
+        .codex_bin
+        .value
+        .clone()
+        .map_or_else(|| OsString::from(CODEX_BIN), OsString::from)
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((idx, _)) => format!("{}…", &text[..idx]),
+        None => text.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_result_extractors() {
+        assert_eq!("last_line".parse(), Ok(ResultExtractor::LastLine));
+        assert_eq!(
+            "json:result".parse(),
+            Ok(ResultExtractor::JsonField("result".to_owned()))
+        );
+        assert!("json:".parse::<ResultExtractor>().is_err());
+        assert!("regex".parse::<ResultExtractor>().is_err());
+    }
+
+    #[test]
+    fn truncates_on_char_boundary() {
+        assert_eq!(truncate_chars("任务完成", 2), "任务…");
+        assert_eq!(truncate_chars("short", 10), "short");
+    }
+}
@@ -0,0 +1,55 @@
+use std::ffi::OsString;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct UsageError(pub String);
+
+/// 将子命令参数转换为 UTF-8 字符串，子命令只接受可读的参数
+pub fn to_strings(args: &[OsString]) -> Result<Vec<String>, UsageError> {
+    args.iter()
+        .map(|arg| {
+            arg.to_str()
+                .map(str::to_owned)
+                .ok_or_else(|| UsageError(format!("argument is not valid UTF-8: {arg:?}")))
+        })
+        .collect()
+}
+
+/// 读取 `--flag value` 形式中紧跟在标志后的值
+pub fn take_value<'a, I>(flag: &str, iter: &mut I) -> Result<&'a str, UsageError>
+where
+    I: Iterator<Item = &'a String>,
+{
+    iter.next()
+        .map(String::as_str)
+        .ok_or_else(|| UsageError(format!("{flag} requires a value")))
+}
+
+pub fn unknown_argument(arg: &str) -> UsageError {
+    UsageError(format!("unknown argument: {arg}"))
+}
+
+/// 将表头与各行按列左对齐拼成文本表格，每行末尾的空白会被去掉
+pub fn render_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|cell| cell.len()).collect();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut output = String::new();
+    let header: Vec<String> = header.iter().map(|cell| (*cell).to_owned()).collect();
+    for row in std::iter::once(&header).chain(rows) {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+    output
+}
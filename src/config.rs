@@ -1,14 +1,57 @@
 use std::time::Duration;
 
 pub const CODEX_BIN: &str = "codex";
+pub const DEFAULT_BACKEND: &str = "codex";
+pub const CODEX_BIN_ENV: &str = "CODEX_WARDEN_CODEX_BIN";
+pub const CONFIG_DIR_NAME: &str = "codex-warden";
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+pub const PROJECT_CONFIG_FILE_NAME: &str = ".codex-warden.toml";
+pub const HISTORY_FILE_NAME: &str = "history.jsonl";
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
 pub const SHARED_NAMESPACE: &str = "codex-task";
 pub const SHARED_MEMORY_SIZE: usize = 4 * 1024 * 1024;
+/// 通知段名称为 `{namespace}{NOTIFY_SEGMENT_SUFFIX}`，与注册表共用命名空间
+pub const NOTIFY_SEGMENT_SUFFIX: &str = "-notify";
+pub const NOTIFY_SEGMENT_SIZE: usize = 4096;
+/// 通知段末尾的并发槽位数，也是 `max_concurrent` 的上限
+pub const CONCURRENCY_SLOTS: usize = 64;
 
 pub const WAIT_INTERVAL_ENV: &str = "CODEX_WARDEN_WAIT_INTERVAL_SEC";
 pub const LEGACY_WAIT_INTERVAL_ENV: &str = "CODEX_WORKER_WAIT_INTERVAL_SEC";
 pub const DEBUG_ENV: &str = "CODEX_WARDEN_DEBUG";
 pub const LEGACY_DEBUG_ENV: &str = "CODEX_WORKER_DEBUG";
+pub const OUTPUT_MODE_ENV: &str = "CODEX_WARDEN_OUTPUT";
+pub const LANG_ENV: &str = "CODEX_WARDEN_LANG";
+/// 未显式指定语言时依次检查的系统 locale 变量
+pub const LOCALE_ENVS: [&str; 3] = ["LC_ALL", "LC_MESSAGES", "LANG"];
+pub const MAX_CONCURRENT_ENV: &str = "CODEX_WARDEN_MAX_CONCURRENT";
+pub const HEARTBEAT_TIMEOUT_ENV: &str = "CODEX_WARDEN_HEARTBEAT_TIMEOUT";
+pub const DETACHED_CHILD_ENV: &str = "CODEX_WARDEN_DETACHED_CHILD";
 
 pub const MAX_RECORD_AGE: Duration = Duration::from_secs(12 * 60 * 60);
 pub const WAIT_INTERVAL_DEFAULT: Duration = Duration::from_secs(30);
 pub const MAX_WAIT_DURATION: Duration = Duration::from_secs(24 * 60 * 60);
+pub const SUMMARY_LINES: usize = 20;
+/// 与 coreutils `timeout` 一致：任务因 `--timeout` 或 `--idle-timeout` 被终止时监督进程的退出码
+pub const TIMEOUT_EXIT_CODE: i32 = 124;
+pub const TIMED_OUT_RESULT: &str = "timed_out";
+pub const IDLE_TIMEOUT_RESULT: &str = "idle_timeout";
+/// `--retries` 第一次重试前的默认等待时间，之后每次翻倍直到上限
+pub const RETRY_BACKOFF_DEFAULT: Duration = Duration::from_secs(10);
+pub const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(10 * 60);
+/// 重试等待期间检查取消与中断的间隔
+pub const RETRY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// 看门狗检查终止信号的间隔；第一个信号已由处理器立即转发，这里只影响后续升级
+pub const SIGNAL_POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// 监督进程写入心跳（连同最近输出时间）的间隔
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+/// 心跳超过该时长未更新即视为监督进程失去响应
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(2 * 60);
+/// `wait` 的退出码：有任务失败或被取消
+pub const WAIT_EXIT_FAILED: i32 = 2;
+/// `wait` 的退出码：达到最长等待时间时仍有任务在运行
+pub const WAIT_EXIT_TIMED_OUT: i32 = 3;
+/// `wait` 的退出码：无法访问任务注册表
+pub const WAIT_EXIT_REGISTRY_ERROR: u8 = 4;
+pub const RESULT_SCAN_LINES: usize = 20;
+pub const RESULT_MAX_CHARS: usize = 2000;
@@ -0,0 +1,101 @@
+use crate::backend::Backend;
+use crate::logging::debug;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 解析后的被委托可执行文件
+#[derive(Debug, Clone)]
+pub struct Delegate {
+    pub path: PathBuf,
+    default_args: Vec<String>,
+    version_args: Vec<String>,
+}
+
+impl Delegate {
+    /// 按后端配置的 `binary` 定位可执行文件：绝对路径直接使用，否则在 PATH 中查找
+    pub fn resolve(backend: &Backend) -> io::Result<Self> {
+        let path = find_executable(Path::new(&backend.binary)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "{} binary {} not found",
+                    backend.name,
+                    backend.binary.to_string_lossy()
+                ),
+            )
+        })?;
+        debug(format!(
+            "resolved {} binary {}",
+            backend.name,
+            path.display()
+        ));
+        Ok(Self {
+            path,
+            default_args: backend.default_args.clone(),
+            version_args: backend.version_args.clone(),
+        })
+    }
+
+    /// 已带上后端默认参数的命令
+    pub fn command(&self) -> Command {
+        let mut command = Command::new(&self.path);
+        command.args(&self.default_args);
+        command
+    }
+
+    pub fn version_command(&self) -> Command {
+        let mut command = Command::new(&self.path);
+        command.args(&self.version_args);
+        command
+    }
+
+    /// 运行版本检查命令并返回首行输出，失败时返回 `None`
+    pub fn version(&self) -> Option<String> {
+        let output = self.version_command().output().ok()?;
+        if !output.status.success() {
+            debug(format!(
+                "version probe for {} exited with {}",
+                self.path.display(),
+                output.status
+            ));
+            return None;
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .map(str::to_owned)
+    }
+}
+
+/// 含路径分隔符的值按路径处理，否则在 PATH 中查找
+fn find_executable(spec: &Path) -> Option<PathBuf> {
+    if spec.components().count() > 1 || spec.is_absolute() {
+        return spec.is_file().then(|| spec.to_path_buf());
+    }
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| {
+        executable_candidates(&dir.join(spec))
+            .into_iter()
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+fn executable_candidates(base: &Path) -> Vec<PathBuf> {
+    #[cfg(windows)]
+    {
+        let mut candidates = vec![base.to_path_buf()];
+        let exts = std::env::var("PATHEXT").unwrap_or_else(|_| ".EXE;.CMD;.BAT".to_owned());
+        for ext in exts.split(';').filter(|ext| !ext.is_empty()) {
+            let mut name = base.as_os_str().to_owned();
+            name.push(ext);
+            candidates.push(PathBuf::from(name));
+        }
+        candidates
+    }
+    #[cfg(not(windows))]
+    {
+        vec![base.to_path_buf()]
+    }
+}
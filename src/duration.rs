@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+/// 超过约一百年的时长一律拒绝，避免调用方与 `Instant` 相加时溢出
+pub const MAX_DURATION: Duration = Duration::from_secs(100 * 365 * 86400);
+
+/// 解析 `250ms`、`90s`、`5m`、`12h`、`1d` 以及 `1h30m` 这类组合；纯数字按秒处理
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let text = value.trim();
+    if text.is_empty() {
+        return Err("empty duration".to_owned());
+    }
+    let too_large = || format!("duration {value} is too large");
+    if let Ok(seconds) = text.parse::<u64>() {
+        return Some(Duration::from_secs(seconds))
+            .filter(|duration| *duration <= MAX_DURATION)
+            .ok_or_else(too_large);
+    }
+
+    let mut total = Duration::ZERO;
+    let mut rest = text;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or_else(|| format!("duration {value} is missing a unit"))?;
+        if digits == 0 {
+            return Err(format!("invalid duration {value}"));
+        }
+        let number: f64 = rest[..digits]
+            .parse()
+            .map_err(|_| format!("invalid duration {value}"))?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let multiplier = match &rest[..unit_len] {
+            "ms" => 0.001,
+            "s" | "sec" | "secs" => 1.0,
+            "m" | "min" | "mins" => 60.0,
+            "h" | "hr" | "hrs" => 3600.0,
+            "d" => 86400.0,
+            unit => return Err(format!("unknown duration unit {unit} in {value}")),
+        };
+        total = Duration::try_from_secs_f64(number * multiplier)
+            .ok()
+            .and_then(|part| total.checked_add(part))
+            .filter(|total| *total <= MAX_DURATION)
+            .ok_or_else(too_large)?;
+        rest = &rest[unit_len..];
+    }
+    Ok(total)
+}
+
+/// 以最大的整数单位输出，例如 `12h`、`90s`、`250ms`
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis == 0 {
+        return "0s".to_owned();
+    }
+    if !millis.is_multiple_of(1000) {
+        return format!("{millis}ms");
+    }
+    let seconds = duration.as_secs();
+    match seconds {
+        s if s.is_multiple_of(86400) => format!("{}d", s / 86400),
+        s if s.is_multiple_of(3600) => format!("{}h", s / 3600),
+        s if s.is_multiple_of(60) => format!("{}m", s / 60),
+        s => format!("{s}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_units() {
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(parse_duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(300)));
+        assert_eq!(parse_duration("12h"), Ok(Duration::from_secs(43200)));
+        assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+    }
+
+    #[test]
+    fn parses_compound_and_fractional_values() {
+        assert_eq!(parse_duration("1h30m"), Ok(Duration::from_secs(5400)));
+        assert_eq!(parse_duration("1.5s"), Ok(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn rejects_overflowing_values() {
+        assert!(parse_duration("99999999999999999999d").is_err());
+        assert!(parse_duration("18446744073709551615s1s").is_err());
+        assert!(parse_duration("18446744073709551615").is_err());
+    }
+
+    #[test]
+    fn rejects_malformed_values() {
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("ten").is_err());
+        assert!(parse_duration("5w").is_err());
+        assert!(parse_duration("m5").is_err());
+    }
+
+    #[test]
+    fn formats_with_largest_unit() {
+        assert_eq!(format_duration(Duration::from_secs(43200)), "12h");
+        assert_eq!(format_duration(Duration::from_secs(90)), "90s");
+        assert_eq!(format_duration(Duration::from_millis(250)), "250ms");
+    }
+}
@@ -0,0 +1,187 @@
+use crate::backend::Backend;
+use crate::duration::{format_duration, parse_duration};
+use crate::platform;
+use crate::task_record::TaskRecord;
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// 终止任务时可以发送的信号；Windows 上一律直接结束进程
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopSignal {
+    Int,
+    Term,
+    Kill,
+}
+
+impl StopSignal {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StopSignal::Int => "SIGINT",
+            StopSignal::Term => "SIGTERM",
+            StopSignal::Kill => "SIGKILL",
+        }
+    }
+}
+
+impl FromStr for StopSignal {
+    type Err = String;
+
+    /// 接受 `SIGTERM`、`TERM`、`term` 等写法
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let upper = value.trim().to_ascii_uppercase();
+        match upper.strip_prefix("SIG").unwrap_or(&upper) {
+            "INT" => Ok(StopSignal::Int),
+            "TERM" => Ok(StopSignal::Term),
+            "KILL" => Ok(StopSignal::Kill),
+            _ => Err(format!(
+                "unsupported signal {value}, expected SIGINT|SIGTERM|SIGKILL"
+            )),
+        }
+    }
+}
+
+/// 升级策略中的一步：发送 `signal` 后最多等待 `grace` 让进程退出
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscalationStep {
+    pub signal: StopSignal,
+    pub grace: Duration,
+}
+
+/// 依次尝试的信号序列，例如 `SIGINT:5s,SIGTERM:10s,SIGKILL`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EscalationPolicy {
+    pub steps: Vec<EscalationStep>,
+}
+
+impl Default for EscalationPolicy {
+    /// 先给 Codex 机会保存会话状态，以便之后 `exec resume`
+    fn default() -> Self {
+        Self {
+            steps: vec![
+                EscalationStep {
+                    signal: StopSignal::Int,
+                    grace: Duration::from_secs(5),
+                },
+                EscalationStep {
+                    signal: StopSignal::Term,
+                    grace: Duration::from_secs(10),
+                },
+                EscalationStep {
+                    signal: StopSignal::Kill,
+                    grace: Duration::ZERO,
+                },
+            ],
+        }
+    }
+}
+
+impl EscalationPolicy {
+    /// `kill --force`：直接 SIGKILL
+    pub fn immediate() -> Self {
+        Self {
+            steps: vec![EscalationStep {
+                signal: StopSignal::Kill,
+                grace: Duration::ZERO,
+            }],
+        }
+    }
+
+    /// `kill --grace`：SIGTERM 后等待 `grace` 再 SIGKILL
+    pub fn term_then_kill(grace: Duration) -> Self {
+        Self {
+            steps: vec![
+                EscalationStep {
+                    signal: StopSignal::Term,
+                    grace,
+                },
+                EscalationStep {
+                    signal: StopSignal::Kill,
+                    grace: Duration::ZERO,
+                },
+            ],
+        }
+    }
+}
+
+impl FromStr for EscalationPolicy {
+    type Err = String;
+
+    /// 逗号分隔的 `信号[:时长]`；省略时长表示不等待，只允许出现在最后一步
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let mut steps = Vec::new();
+        for part in value.split(',') {
+            let (signal, grace) = match part.split_once(':') {
+                Some((signal, grace)) => (signal, Some(grace)),
+                None => (part, None),
+            };
+            let signal: StopSignal = signal.parse()?;
+            let grace = grace
+                .map(parse_duration)
+                .transpose()
+                .map_err(|err| format!("invalid stop policy {value}: {err}"))?;
+            steps.push(EscalationStep {
+                signal,
+                grace: grace.unwrap_or(Duration::ZERO),
+            });
+        }
+        if steps.iter().rev().skip(1).any(|step| step.grace.is_zero()) {
+            return Err(format!(
+                "invalid stop policy {value}: only the last step may omit its grace period"
+            ));
+        }
+        Ok(Self { steps })
+    }
+}
+
+impl fmt::Display for EscalationPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, step) in self.steps.iter().enumerate() {
+            if index > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}", step.signal.as_str())?;
+            if !step.grace.is_zero() {
+                write!(f, ":{}", format_duration(step.grace))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'de> Deserialize<'de> for EscalationPolicy {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// 按任务所属后端的策略终止任务，返回最终让进程退出的信号；进程早已退出时返回 `None`
+pub fn stop_task(record: &TaskRecord) -> Option<StopSignal> {
+    let policy = Backend::load(&record.backend)
+        .map(|backend| backend.stop_policy)
+        .unwrap_or_default();
+    platform::stop_process(record.pid, &policy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_prints_policies() {
+        let policy: EscalationPolicy = "SIGINT:5s,term:10s,KILL".parse().unwrap();
+        assert_eq!(policy, EscalationPolicy::default());
+        assert_eq!(policy.to_string(), "SIGINT:5s,SIGTERM:10s,SIGKILL");
+        assert_eq!(
+            "SIGTERM:250ms,SIGKILL".parse(),
+            Ok(EscalationPolicy::term_then_kill(Duration::from_millis(250)))
+        );
+        assert!("SIGINT,SIGKILL".parse::<EscalationPolicy>().is_err());
+        assert!("SIGHUP:1s".parse::<EscalationPolicy>().is_err());
+    }
+}
@@ -0,0 +1,384 @@
+use crate::cli::{self, UsageError};
+use crate::config::{CONFIG_DIR_NAME, DEFAULT_HISTORY_LIMIT, HISTORY_FILE_NAME};
+use crate::duration::parse_duration;
+use crate::logging::warn;
+use crate::registry::CleanupReason;
+use crate::status::format_age;
+use crate::task_record::{self, TaskRecord};
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum HistoryError {
+    #[error("{0}")]
+    Usage(#[from] UsageError),
+    #[error("cannot determine the user data directory")]
+    NoDataDir,
+    #[error("failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("serialization failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+/// 历史文件中的一行：任务结束时的完整记录
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub recorded_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub record: TaskRecord,
+}
+
+/// 任务的最终结果，与 `wait` 报告中的判定一致
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl Outcome {
+    fn of(record: &TaskRecord) -> Self {
+        match record.cleanup_reason.as_deref() {
+            Some(reason) if reason == CleanupReason::CancelledByUser.as_str() => Outcome::Cancelled,
+            Some(_) => Outcome::Failed,
+            None if task_record::is_success(None, record.exit_code, record.result.as_deref()) => {
+                Outcome::Succeeded
+            }
+            None => Outcome::Failed,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Outcome::Succeeded => "succeeded",
+            Outcome::Failed => "failed",
+            Outcome::Cancelled => "cancelled",
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self, UsageError> {
+        match value.to_ascii_lowercase().as_str() {
+            "succeeded" | "success" => Ok(Outcome::Succeeded),
+            "failed" => Ok(Outcome::Failed),
+            "cancelled" | "canceled" => Ok(Outcome::Cancelled),
+            _ => Err(UsageError(format!(
+                "invalid status {value}, expected succeeded|failed|cancelled"
+            ))),
+        }
+    }
+}
+
+/// `~/.local/share/codex-warden/history.jsonl`（Windows 下位于 `%LOCALAPPDATA%`）
+pub fn history_path() -> Option<PathBuf> {
+    data_home().map(|dir| dir.join(CONFIG_DIR_NAME).join(HISTORY_FILE_NAME))
+}
+
+fn data_home() -> Option<PathBuf> {
+    #[cfg(windows)]
+    {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    }
+    #[cfg(not(windows))]
+    {
+        std::env::var_os("XDG_DATA_HOME")
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+    }
+}
+
+/// 追加一条最终记录；历史只是辅助信息，失败时仅给出警告
+pub fn append(record: &TaskRecord) {
+    let Some(path) = history_path() else {
+        return;
+    };
+    if let Err(err) = try_append(&path, record) {
+        warn(format!("failed to write history {}: {err}", path.display()));
+    }
+}
+
+fn try_append(path: &Path, record: &TaskRecord) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let entry = HistoryEntry {
+        recorded_at: Utc::now(),
+        record: record.clone(),
+    };
+    let mut line = serde_json::to_string(&entry).map_err(io::Error::other)?;
+    line.push('\n');
+    // 整行一次写入，配合 append 模式避免多个进程的记录交错
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())
+}
+
+/// 读取全部历史；同一任务可能被多次写入（例如清理后监督进程又补写结果），只保留最后一条
+fn load(path: &Path) -> Result<Vec<HistoryEntry>, HistoryError> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(source) => {
+            return Err(HistoryError::Io {
+                path: path.to_path_buf(),
+                source,
+            });
+        }
+    };
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|source| HistoryError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: HistoryEntry = match serde_json::from_str(&line) {
+            Ok(entry) => entry,
+            Err(err) => {
+                warn(format!("skipping history line {}: {err}", number + 1));
+                continue;
+            }
+        };
+        match index.get(&entry.record.log_id) {
+            Some(&position) => entries[position] = entry,
+            None => {
+                index.insert(entry.record.log_id.clone(), entries.len());
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+#[derive(Debug)]
+struct HistoryOptions {
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    status: Option<Outcome>,
+    backend: Option<String>,
+    cwd: Option<PathBuf>,
+    limit: usize,
+    json: bool,
+}
+
+impl HistoryOptions {
+    fn parse(args: &[String], now: DateTime<Utc>) -> Result<Self, UsageError> {
+        let mut options = HistoryOptions {
+            since: None,
+            until: None,
+            status: None,
+            backend: None,
+            cwd: None,
+            limit: DEFAULT_HISTORY_LIMIT,
+            json: false,
+        };
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--since" => {
+                    options.since = Some(parse_time_bound(cli::take_value(arg, &mut iter)?, now)?)
+                }
+                "--until" => {
+                    options.until = Some(parse_time_bound(cli::take_value(arg, &mut iter)?, now)?)
+                }
+                "--status" => {
+                    options.status = Some(Outcome::parse(cli::take_value(arg, &mut iter)?)?)
+                }
+                "--backend" => options.backend = Some(cli::take_value(arg, &mut iter)?.to_owned()),
+                "--cwd" => options.cwd = Some(absolute(cli::take_value(arg, &mut iter)?)),
+                "--limit" => {
+                    let value = cli::take_value(arg, &mut iter)?;
+                    options.limit = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|limit| *limit > 0)
+                        .ok_or_else(|| {
+                            UsageError(format!("--limit expects a positive count, got {value}"))
+                        })?;
+                }
+                "--json" => options.json = true,
+                other => return Err(cli::unknown_argument(other)),
+            }
+        }
+        Ok(options)
+    }
+
+    fn matches(&self, entry: &HistoryEntry) -> bool {
+        let record = &entry.record;
+        let finished = finished_at(entry);
+        self.since.is_none_or(|since| finished >= since)
+            && self.until.is_none_or(|until| finished <= until)
+            && self
+                .status
+                .is_none_or(|status| Outcome::of(record) == status)
+            && self
+                .backend
+                .as_ref()
+                .is_none_or(|backend| &record.backend == backend)
+            && self.cwd.as_ref().is_none_or(|cwd| {
+                record
+                    .cwd
+                    .as_ref()
+                    .is_some_and(|task_cwd| Path::new(task_cwd).starts_with(cwd))
+            })
+    }
+}
+
+/// 接受 RFC 3339 时间、`YYYY-MM-DD`（本地零点）或表示“多久以前”的时长，例如 `2h`
+fn parse_time_bound(value: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, UsageError> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(time.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        && let Some(midnight) = date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|time| Local.from_local_datetime(&time).earliest())
+    {
+        return Ok(midnight.with_timezone(&Utc));
+    }
+    let ago = parse_duration(value)
+        .ok()
+        .and_then(|duration| chrono::Duration::from_std(duration).ok())
+        .ok_or_else(|| {
+            UsageError(format!(
+                "invalid time {value}, expected RFC 3339, YYYY-MM-DD or a duration such as 2h"
+            ))
+        })?;
+    Ok(now - ago)
+}
+
+fn absolute(path: &str) -> PathBuf {
+    let path = PathBuf::from(path);
+    if path.is_absolute() {
+        return path;
+    }
+    std::env::current_dir()
+        .map(|cwd| cwd.join(&path))
+        .unwrap_or(path)
+}
+
+fn finished_at(entry: &HistoryEntry) -> DateTime<Utc> {
+    entry.record.completed_at.unwrap_or(entry.recorded_at)
+}
+
+/// `codex-warden history`：查询已经离开注册表的任务
+pub fn run(args: &[OsString]) -> Result<(), HistoryError> {
+    let options = HistoryOptions::parse(&cli::to_strings(args)?, Utc::now())?;
+    let path = history_path().ok_or(HistoryError::NoDataDir)?;
+    let mut entries: Vec<HistoryEntry> = load(&path)?
+        .into_iter()
+        .filter(|entry| options.matches(entry))
+        .collect();
+    entries.sort_by_key(finished_at);
+    let skip = entries.len().saturating_sub(options.limit);
+    let entries = &entries[skip..];
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(entries)?);
+    } else {
+        print!("{}", render_table(entries));
+    }
+    Ok(())
+}
+
+fn render_table(entries: &[HistoryEntry]) -> String {
+    if entries.is_empty() {
+        return "No matching history.\n".to_owned();
+    }
+
+    let header = [
+        "FINISHED", "PID", "BACKEND", "STATUS", "EXIT", "DURATION", "CWD", "LOG",
+    ];
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .map(|entry| {
+            let record = &entry.record;
+            let finished = finished_at(entry);
+            vec![
+                finished
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+                record.pid.to_string(),
+                record.backend.clone(),
+                Outcome::of(record).label().to_owned(),
+                record
+                    .exit_code
+                    .map_or_else(|| "-".to_owned(), |code| code.to_string()),
+                format_age(finished.signed_duration_since(record.started_at)),
+                record.cwd.clone().unwrap_or_else(|| "-".to_owned()),
+                record.log_path.clone(),
+            ]
+        })
+        .collect();
+    cli::render_table(&header, &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_time_bounds() {
+        let now = DateTime::parse_from_rfc3339("2025-10-20T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(
+            parse_time_bound("2h", now).unwrap(),
+            now - chrono::Duration::hours(2)
+        );
+        assert_eq!(
+            parse_time_bound("2025-10-19T08:30:00Z", now).unwrap(),
+            now - chrono::Duration::minutes(27 * 60 + 30)
+        );
+        assert!(parse_time_bound("2025-10-19", now).is_ok());
+        assert!(parse_time_bound("yesterday", now).is_err());
+    }
+
+    #[test]
+    fn keeps_latest_entry_per_task() {
+        let dir = std::env::temp_dir().join(format!("codex-warden-history-{}", std::process::id()));
+        let path = dir.join("history.jsonl");
+        let _ = fs::remove_dir_all(&dir);
+        let record = TaskRecord::new(
+            7,
+            Utc::now(),
+            "task-7".to_owned(),
+            "/tmp/task-7.log".to_owned(),
+            None,
+        );
+        try_append(&path, &record.clone().with_cleanup_reason("process_exited")).unwrap();
+        try_append(
+            &path,
+            &record.clone().mark_completed(None, Some(0), Utc::now()),
+        )
+        .unwrap();
+
+        let entries = load(&path).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(Outcome::of(&entries[0].record), Outcome::Succeeded);
+
+        // 被信号终止的任务没有退出码
+        let killed = record.mark_completed(
+            Some("failed_without_exit_code".to_owned()),
+            None,
+            Utc::now(),
+        );
+        assert_eq!(Outcome::of(&killed), Outcome::Failed);
+    }
+}
@@ -0,0 +1,228 @@
+use crate::cli::UsageError;
+use std::fmt::Display;
+use std::str::FromStr;
+
+/// `wait` 报告使用的语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    En,
+    ZhCn,
+}
+
+impl FromStr for Lang {
+    type Err = UsageError;
+
+    /// 接受 `en`、`zh-CN` 以及 `zh_CN.UTF-8` 这类 locale 写法
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized = value
+            .split('.')
+            .next()
+            .unwrap_or_default()
+            .replace('_', "-")
+            .to_ascii_lowercase();
+        match normalized.split('-').next() {
+            Some("en") => Ok(Lang::En),
+            Some("zh") => Ok(Lang::ZhCn),
+            _ => Err(UsageError(format!(
+                "unsupported language {value}, expected en|zh-CN"
+            ))),
+        }
+    }
+}
+
+impl Lang {
+    /// 系统 locale（`LANG` 等）中无法识别的值一律回退到英文
+    pub fn from_locale(value: &str) -> Self {
+        value.parse().unwrap_or_default()
+    }
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::ZhCn => "zh-CN",
+        }
+    }
+
+    pub fn messages(self) -> &'static Messages {
+        match self {
+            Lang::En => &EN,
+            Lang::ZhCn => &ZH_CN,
+        }
+    }
+}
+
+/// `wait` 的全部面向用户的文本；带 `{name}` 的条目需经 [`fill`] 填充
+pub struct Messages {
+    pub not_provided: &'static str,
+    pub task_event: &'static str,
+    pub word_completed: &'static str,
+    pub word_cancelled: &'static str,
+    pub word_failed: &'static str,
+    pub label_log_file: &'static str,
+    pub label_result: &'static str,
+    pub label_error: &'static str,
+    pub label_status: &'static str,
+    pub label_version: &'static str,
+    pub label_completed_at: &'static str,
+    pub label_attempts: &'static str,
+    pub summary_cancelled: &'static str,
+    pub summary_cleaned_up: &'static str,
+    pub summary_success_empty: &'static str,
+    pub summary_failure_empty: &'static str,
+    pub report_title: &'static str,
+    pub report_timed_out: &'static str,
+    pub completed_heading: &'static str,
+    pub no_completed: &'static str,
+    pub stats_heading: &'static str,
+    pub stats_total: &'static str,
+    pub stats_succeeded: &'static str,
+    pub stats_failed: &'static str,
+    pub stats_cancelled: &'static str,
+    pub stats_by_backend: &'static str,
+    pub stats_backend_count: &'static str,
+    pub stats_elapsed: &'static str,
+    pub logs_heading: &'static str,
+    pub no_logs: &'static str,
+    pub running_heading: &'static str,
+    pub running_entry: &'static str,
+    pub queued_entry: &'static str,
+    pub continue_hint: &'static str,
+    pub reading_heading: &'static str,
+    pub reading_batches: &'static str,
+    pub reading_offset_limit: &'static str,
+    pub reading_no_paths: &'static str,
+    pub reading_paths: &'static str,
+    pub reading_next: &'static str,
+    pub unit_hours: &'static str,
+    pub unit_minutes: &'static str,
+    pub unit_seconds: &'static str,
+    pub unit_separator: &'static str,
+}
+
+static EN: Messages = Messages {
+    not_provided: "not provided",
+    task_event: "Task {status} PID={pid} (exit_code: {exit_code}) @ {time}",
+    word_completed: "completed",
+    word_cancelled: "cancelled",
+    word_failed: "failed",
+    label_log_file: "Log file",
+    label_result: "Result summary",
+    label_error: "Error summary",
+    label_status: "Status",
+    label_version: "Version",
+    label_completed_at: "Completed at",
+    label_attempts: "Attempts",
+    summary_cancelled: "The task was cancelled by the user.",
+    summary_cleaned_up: "Task was cleaned up: {reason}",
+    summary_success_empty: "The task succeeded but produced no summary.",
+    summary_failure_empty: "The task failed without an error summary.",
+    report_title: "Task completion report",
+    report_timed_out: "The maximum wait time was reached while tasks were still running.",
+    completed_heading: "Completed tasks",
+    no_completed: "No completed tasks yet",
+    stats_heading: "Statistics",
+    stats_total: "Total tasks: {count}",
+    stats_succeeded: "Succeeded: {count}",
+    stats_failed: "Failed: {count}",
+    stats_cancelled: "Cancelled: {count}",
+    stats_by_backend: "By backend: {counts}",
+    stats_backend_count: "{backend} {count}",
+    stats_elapsed: "Total time: {duration}",
+    logs_heading: "Full log file paths",
+    no_logs: "No logs available",
+    running_heading: "Tasks still running",
+    running_entry: "PID {pid} (started {started}, silent for {silent}) -> {log_path}",
+    queued_entry: "Task {task_id} queued at position {position} -> {log_path}",
+    continue_hint: "Continue your work based on the results above and check the log files when needed.",
+    reading_heading: "Log reading tips for Claude",
+    reading_batches: "Claude, read large log files in batches instead of requesting everything at once.",
+    reading_offset_limit: "Use the {offset}/{limit} parameters to control the output range and inspect key information section by section.",
+    reading_no_paths: "There are no log files to read yet; try again once tasks have finished.",
+    reading_paths: "Read the logs one by one from these paths:",
+    reading_next: "After each batch, state the next {offset}/{limit} you need or point to a new file path so reading can continue.",
+    unit_hours: "h",
+    unit_minutes: "m",
+    unit_seconds: "s",
+    unit_separator: " ",
+};
+
+static ZH_CN: Messages = Messages {
+    not_provided: "未提供",
+    task_event: "任务{status} PID={pid} (exit_code: {exit_code}) @ {time}",
+    word_completed: "完成",
+    word_cancelled: "已取消",
+    word_failed: "失败",
+    label_log_file: "日志文件",
+    label_result: "结果摘要",
+    label_error: "错误摘要",
+    label_status: "状态",
+    label_version: "版本",
+    label_completed_at: "完成时间",
+    label_attempts: "尝试次数",
+    summary_cancelled: "任务已被用户取消。",
+    summary_cleaned_up: "任务被清理: {reason}",
+    summary_success_empty: "任务成功完成，但未提供摘要。",
+    summary_failure_empty: "任务失败，未提供错误摘要。",
+    report_title: "任务执行完成报告",
+    report_timed_out: "等待已达到最大时长，仍检测到未完成的任务。",
+    completed_heading: "已完成任务列表",
+    no_completed: "暂无完成任务",
+    stats_heading: "执行统计",
+    stats_total: "总任务数: {count}",
+    stats_succeeded: "成功: {count}个",
+    stats_failed: "失败: {count}个",
+    stats_cancelled: "已取消: {count}个",
+    stats_by_backend: "按后端: {counts}",
+    stats_backend_count: "{backend} {count}个",
+    stats_elapsed: "总耗时: {duration}",
+    logs_heading: "完整日志文件路径",
+    no_logs: "无可用日志",
+    running_heading: "仍在运行的任务",
+    running_entry: "PID {pid} (启动于 {started}，已静默 {silent}) -> {log_path}",
+    queued_entry: "任务 {task_id} 排队中，位置 {position} -> {log_path}",
+    continue_hint: "现在请基于上述结果继续你的工作，必要时查看日志文件。",
+    reading_heading: "Claude 日志阅读提示",
+    reading_batches: "Claude，请分批次读取体积较大的日志文件，避免一次性请求全部内容。",
+    reading_offset_limit: "请在读取日志时使用 {offset}/{limit} 参数来控制输出范围，逐段检查关键信息。",
+    reading_no_paths: "当前没有可供阅读的日志文件路径，可在任务完成后再尝试。",
+    reading_paths: "建议按照以下路径逐个读取日志：",
+    reading_next: "读取完一批内容后，请说明下一步需要的 {offset}/{limit} 或指出新的文件路径，以便继续协助你。",
+    unit_hours: "小时",
+    unit_minutes: "分",
+    unit_seconds: "秒",
+    unit_separator: "",
+};
+
+/// 按名称替换模板中的 `{name}` 占位符
+pub fn fill(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut output = template.to_owned();
+    for (name, value) in args {
+        output = output.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_codes_and_locales() {
+        assert_eq!("en".parse::<Lang>().unwrap(), Lang::En);
+        assert_eq!("zh-CN".parse::<Lang>().unwrap(), Lang::ZhCn);
+        assert_eq!("zh_CN.UTF-8".parse::<Lang>().unwrap(), Lang::ZhCn);
+        assert!("fr".parse::<Lang>().is_err());
+        assert_eq!(Lang::from_locale("C.UTF-8"), Lang::En);
+        assert_eq!(Lang::from_locale("de_DE.UTF-8"), Lang::En);
+    }
+
+    #[test]
+    fn fills_named_placeholders() {
+        let text = fill(
+            EN.stats_backend_count,
+            &[("backend", &"codex"), ("count", &3)],
+        );
+        assert_eq!(text, "codex 3");
+    }
+}
@@ -0,0 +1,102 @@
+use crate::cli::{self, UsageError};
+use crate::duration::MAX_DURATION;
+use crate::escalation::{self, EscalationPolicy};
+use crate::history;
+use crate::platform;
+use crate::registry::{CleanupReason, RegistryError, TaskRegistry};
+use std::ffi::OsString;
+use std::time::Duration;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum KillError {
+    #[error("registry error: {0}")]
+    Registry(#[from] RegistryError),
+    #[error("{0}")]
+    Usage(#[from] UsageError),
+    #[error("no task found for {0}")]
+    NotFound(String),
+    #[error("task pid={0} is not running")]
+    NotRunning(u32),
+}
+
+#[derive(Debug)]
+struct KillOptions {
+    selector: String,
+    /// 未指定时使用任务所属后端的 `stop_policy`
+    policy: Option<EscalationPolicy>,
+}
+
+impl KillOptions {
+    fn parse(args: &[String]) -> Result<Self, UsageError> {
+        let mut selector = None;
+        let mut policy = None;
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--force" => policy = Some(EscalationPolicy::immediate()),
+                "--grace" => {
+                    let value = cli::take_value(arg, &mut iter)?;
+                    let grace = value
+                        .parse::<u64>()
+                        .ok()
+                        .map(Duration::from_secs)
+                        .filter(|grace| *grace <= MAX_DURATION)
+                        .ok_or_else(|| {
+                            UsageError(format!("--grace expects whole seconds, got {value}"))
+                        })?;
+                    policy = Some(EscalationPolicy::term_then_kill(grace));
+                }
+                "--policy" => {
+                    let value = cli::take_value(arg, &mut iter)?;
+                    policy = Some(value.parse().map_err(UsageError)?);
+                }
+                other if other.starts_with("--") => return Err(cli::unknown_argument(other)),
+                other if selector.is_none() => selector = Some(other.to_owned()),
+                other => return Err(cli::unknown_argument(other)),
+            }
+        }
+        let selector = selector.ok_or_else(|| {
+            UsageError(
+                "usage: codex-warden kill <task_id|pid> [--force | --grace <secs> | --policy <steps>]"
+                    .to_owned(),
+            )
+        })?;
+        Ok(Self { selector, policy })
+    }
+}
+
+pub fn run(args: &[OsString]) -> Result<(), KillError> {
+    let options = KillOptions::parse(&cli::to_strings(args)?)?;
+    let registry = TaskRegistry::connect()?;
+    let entry = registry
+        .find(&options.selector)?
+        .ok_or_else(|| KillError::NotFound(options.selector.clone()))?;
+    if entry.record.status.is_finished() {
+        return Err(KillError::NotRunning(entry.pid));
+    }
+
+    let record = registry.cancel(
+        &entry.task_id,
+        CleanupReason::CancelledByUser,
+        platform::same_process_alive,
+        &|record| match &options.policy {
+            Some(policy) => platform::stop_process(record.pid, policy),
+            None => escalation::stop_task(record),
+        },
+    )?;
+
+    // 排队中的任务没有子进程，监督进程只会直接退出；运行中的任务在宽限期内可能已被 `wait`
+    // 读走并删除，监督进程之后写不进结果也就不写历史。因此总由这里写入一条，
+    // 监督进程若仍写入了历史，按任务只保留最新的一条
+    history::append(&record);
+
+    println!(
+        "Cancelled task {} pid={} stopped_by={} (log: {})",
+        entry.task_id,
+        entry.pid,
+        record.stopped_by.as_deref().unwrap_or("-"),
+        record.log_path
+    );
+    Ok(())
+}
@@ -0,0 +1,194 @@
+use crate::cli::UsageError;
+use crate::duration::parse_duration;
+use crate::retry::RetryPolicy;
+use crate::settings;
+use std::ffi::OsString;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Codex 输出在 codex-warden 自身标准输出上的呈现方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputMode {
+    /// 同时写入日志并镜像到标准输出/标准错误
+    Mirror,
+    /// 仅写入日志（SPEC 默认行为）
+    #[default]
+    Quiet,
+    /// 仅写入日志，结束后打印日志末尾若干行
+    Summary,
+}
+
+impl FromStr for OutputMode {
+    type Err = UsageError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "mirror" => Ok(OutputMode::Mirror),
+            "quiet" => Ok(OutputMode::Quiet),
+            "summary" => Ok(OutputMode::Summary),
+            _ => Err(UsageError(format!(
+                "invalid output mode {value}, expected mirror|quiet|summary"
+            ))),
+        }
+    }
+}
+
+impl OutputMode {
+    /// 命令行选项优先，其次是环境变量与配置文件，最后回退到默认的 quiet
+    pub fn resolve(flag: Option<OutputMode>) -> OutputMode {
+        flag.unwrap_or_else(|| settings::get().output.value)
+    }
+}
+
+/// 位于 Codex 参数之前、由 codex-warden 自己消费的启动选项
+#[derive(Debug, Default)]
+pub struct LaunchOptions {
+    pub detach: bool,
+    pub output: Option<OutputMode>,
+    pub backend: Option<String>,
+    pub tags: Vec<String>,
+    pub group: Option<String>,
+    /// 任务的最长运行时间，超时后由监督进程自行终止
+    pub timeout: Option<Duration>,
+    /// 子进程连续无输出的最长时间，超过即视为卡死并终止
+    pub idle_timeout: Option<Duration>,
+    pub retry: RetryPolicy,
+}
+
+impl LaunchOptions {
+    /// 解析开头的 codex-warden 选项，返回选项及其占用的参数个数；
+    /// 遇到第一个不认识的参数即停止，其余参数原样交给 Codex
+    pub fn parse(args: &[OsString]) -> Result<(Self, usize), UsageError> {
+        let mut options = LaunchOptions::default();
+        let mut consumed = 0;
+        while let Some(arg) = args.get(consumed) {
+            match arg.to_str() {
+                Some("--detach") => options.detach = true,
+                Some(flag @ "--output") => {
+                    consumed += 1;
+                    options.output = Some(flag_value(flag, args.get(consumed))?.parse()?);
+                }
+                Some(flag @ "--backend") => {
+                    consumed += 1;
+                    options.backend = Some(flag_value(flag, args.get(consumed))?.to_owned());
+                }
+                Some(flag @ "--tag") => {
+                    consumed += 1;
+                    options
+                        .tags
+                        .push(flag_value(flag, args.get(consumed))?.to_owned());
+                }
+                Some(flag @ "--group") => {
+                    consumed += 1;
+                    options.group = Some(flag_value(flag, args.get(consumed))?.to_owned());
+                }
+                Some(flag @ "--timeout") => {
+                    consumed += 1;
+                    options.timeout = Some(positive_duration(flag, args.get(consumed))?);
+                }
+                Some(flag @ "--idle-timeout") => {
+                    consumed += 1;
+                    options.idle_timeout = Some(positive_duration(flag, args.get(consumed))?);
+                }
+                Some(flag @ "--retries") => {
+                    consumed += 1;
+                    let value = flag_value(flag, args.get(consumed))?;
+                    options.retry.retries = value
+                        .parse()
+                        .map_err(|_| UsageError(format!("{flag} expects a count, got {value}")))?;
+                }
+                Some(flag @ "--retry-on") => {
+                    consumed += 1;
+                    options
+                        .retry
+                        .conditions
+                        .push(flag_value(flag, args.get(consumed))?.parse()?);
+                }
+                Some(flag @ "--retry-backoff") => {
+                    consumed += 1;
+                    options.retry.backoff = positive_duration(flag, args.get(consumed))?;
+                }
+                _ => break,
+            }
+            consumed += 1;
+        }
+        if options.retry.retries == 0 && !options.retry.conditions.is_empty() {
+            return Err(UsageError("--retry-on requires --retries".to_owned()));
+        }
+        Ok((options, consumed))
+    }
+}
+
+fn flag_value<'a>(flag: &str, value: Option<&'a OsString>) -> Result<&'a str, UsageError> {
+    value
+        .and_then(|value| value.to_str())
+        .ok_or_else(|| UsageError(format!("{flag} requires a value")))
+}
+
+fn positive_duration(flag: &str, value: Option<&OsString>) -> Result<Duration, UsageError> {
+    let limit = parse_duration(flag_value(flag, value)?)
+        .map_err(|err| UsageError(format!("{flag}: {err}")))?;
+    if limit.is_zero() {
+        return Err(UsageError(format!("{flag} must be greater than zero")));
+    }
+    Ok(limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn os_args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn stops_at_first_codex_argument() {
+        let args = os_args(&[
+            "--detach",
+            "--backend",
+            "claude",
+            "--output",
+            "summary",
+            "--tag",
+            "lint",
+            "--tag",
+            "ci",
+            "--group",
+            "pr-42",
+            "--timeout",
+            "90m",
+            "--idle-timeout",
+            "15m",
+            "--retries",
+            "3",
+            "--retry-on",
+            "exit:1,2",
+            "--retry-backoff",
+            "30s",
+            "exec",
+            "--output",
+            "x",
+        ]);
+        let (options, consumed) = LaunchOptions::parse(&args).unwrap();
+        assert!(options.detach);
+        assert_eq!(options.backend.as_deref(), Some("claude"));
+        assert_eq!(options.output, Some(OutputMode::Summary));
+        assert_eq!(options.tags, ["lint", "ci"]);
+        assert_eq!(options.group.as_deref(), Some("pr-42"));
+        assert_eq!(options.timeout, Some(Duration::from_secs(5400)));
+        assert_eq!(options.idle_timeout, Some(Duration::from_secs(900)));
+        assert_eq!(options.retry.retries, 3);
+        assert_eq!(options.retry.conditions.len(), 1);
+        assert_eq!(options.retry.backoff, Duration::from_secs(30));
+        assert_eq!(consumed, 21);
+    }
+
+    #[test]
+    fn rejects_unknown_output_mode() {
+        let args = os_args(&["--output", "loud", "exec"]);
+        assert!(LaunchOptions::parse(&args).is_err());
+        let args = os_args(&["--retry-on", "exit:1", "exec"]);
+        assert!(LaunchOptions::parse(&args).is_err());
+    }
+}
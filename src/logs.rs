@@ -0,0 +1,199 @@
+use crate::cli::{self, UsageError};
+use crate::registry::{RegistryError, TaskRegistry};
+use crate::task_record::TaskStatus;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::thread;
+use std::time::Duration;
+use thiserror::Error;
+
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(500);
+const TAIL_CHUNK_SIZE: usize = 8192;
+
+#[derive(Debug, Error)]
+pub enum LogsError {
+    #[error("registry error: {0}")]
+    Registry(#[from] RegistryError),
+    #[error("{0}")]
+    Usage(#[from] UsageError),
+    #[error("no task found for {0}")]
+    NotFound(String),
+    #[error("cannot read log {path}: {source}")]
+    Io { path: String, source: io::Error },
+}
+
+#[derive(Debug, Default)]
+struct LogsOptions {
+    selector: String,
+    follow: bool,
+    tail: Option<usize>,
+    since: Option<u64>,
+}
+
+impl LogsOptions {
+    fn parse(args: &[String]) -> Result<Self, UsageError> {
+        let mut options = LogsOptions::default();
+        let mut selector = None;
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--follow" | "-f" => options.follow = true,
+                "--tail" => {
+                    let value = cli::take_value(arg, &mut iter)?;
+                    options.tail = Some(value.parse().map_err(|_| {
+                        UsageError(format!("--tail expects a line count, got {value}"))
+                    })?);
+                }
+                "--since" => {
+                    let value = cli::take_value(arg, &mut iter)?;
+                    options.since = Some(value.parse().map_err(|_| {
+                        UsageError(format!("--since expects a byte offset, got {value}"))
+                    })?);
+                }
+                other if other.starts_with("--") => return Err(cli::unknown_argument(other)),
+                other if selector.is_none() => selector = Some(other.to_owned()),
+                other => return Err(cli::unknown_argument(other)),
+            }
+        }
+        options.selector = selector.ok_or_else(|| {
+            UsageError(
+                "usage: codex-warden logs <task_id|pid> [--follow] [--tail N] [--since <offset>]"
+                    .to_owned(),
+            )
+        })?;
+        Ok(options)
+    }
+}
+
+pub fn run(args: &[OsString]) -> Result<(), LogsError> {
+    let options = LogsOptions::parse(&cli::to_strings(args)?)?;
+    let registry = TaskRegistry::connect()?;
+    let entry = registry
+        .find(&options.selector)?
+        .ok_or_else(|| LogsError::NotFound(options.selector.clone()))?;
+    let mut path = entry.record.log_path.clone();
+
+    // 排队中的任务还没有日志；跟随时等它启动
+    if entry.record.status == TaskStatus::Queued
+        && (!options.follow || !wait_while_queued(&registry, &entry.task_id)?)
+    {
+        return Ok(());
+    }
+
+    let mut file = File::open(&path).map_err(io_error(&path))?;
+    let mut offset = match (options.since, options.tail) {
+        (Some(since), _) => since,
+        (None, Some(lines)) => tail_offset(&mut file, lines).map_err(io_error(&path))?,
+        (None, None) => 0,
+    };
+    offset = copy_from(&mut file, offset).map_err(io_error(&path))?;
+
+    if !options.follow {
+        return Ok(());
+    }
+
+    // 持续输出新内容，直到监督进程把任务标记为完成或记录被移除；
+    // 重试时记录改指向新一次尝试的日志，读完旧文件后从头跟随新文件
+    loop {
+        let current = registry
+            .find(&entry.task_id)?
+            .filter(|item| item.task_id == entry.task_id);
+        offset = copy_from(&mut file, offset).map_err(io_error(&path))?;
+        if let Some(current) = &current
+            && current.record.log_path != path
+        {
+            path = current.record.log_path.clone();
+            file = File::open(&path).map_err(io_error(&path))?;
+            offset = copy_from(&mut file, 0).map_err(io_error(&path))?;
+        }
+        if current.is_none_or(|current| current.record.status.is_finished()) {
+            return Ok(());
+        }
+        thread::sleep(FOLLOW_POLL_INTERVAL);
+    }
+}
+
+fn io_error(path: &str) -> impl FnOnce(io::Error) -> LogsError + '_ {
+    move |source| LogsError::Io {
+        path: path.to_owned(),
+        source,
+    }
+}
+
+/// 从 `offset` 开始把文件剩余内容写到标准输出，返回新的偏移量
+pub fn copy_from(file: &mut File, offset: u64) -> io::Result<u64> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut stdout = io::stdout().lock();
+    let copied = io::copy(file, &mut stdout)?;
+    stdout.flush()?;
+    Ok(offset + copied)
+}
+
+/// 等待排队的任务启动；任务在排队期间被取消或移除时返回 `false`
+fn wait_while_queued(registry: &TaskRegistry, task_id: &str) -> Result<bool, LogsError> {
+    loop {
+        match registry.find(task_id)? {
+            Some(entry) if entry.record.status == TaskStatus::Queued => {
+                thread::sleep(FOLLOW_POLL_INTERVAL);
+            }
+            // 在排队中被取消的任务 PID 仍为 0，从未写过日志
+            Some(entry) => return Ok(entry.record.pid != 0),
+            None => return Ok(false),
+        }
+    }
+}
+
+/// 计算最后 `lines` 行的起始偏移量，文件末尾的换行不计为空行
+pub fn tail_offset<R: Read + Seek>(reader: &mut R, lines: usize) -> io::Result<u64> {
+    let len = reader.seek(SeekFrom::End(0))?;
+    if lines == 0 {
+        return Ok(len);
+    }
+
+    let mut buffer = vec![0u8; TAIL_CHUNK_SIZE];
+    let mut position = len;
+    let mut newlines = 0usize;
+    let mut skip_trailing = true;
+    while position > 0 {
+        let chunk = (position as usize).min(TAIL_CHUNK_SIZE);
+        position -= chunk as u64;
+        reader.seek(SeekFrom::Start(position))?;
+        reader.read_exact(&mut buffer[..chunk])?;
+        for idx in (0..chunk).rev() {
+            if buffer[idx] != b'\n' {
+                skip_trailing = false;
+                continue;
+            }
+            if skip_trailing {
+                skip_trailing = false;
+                continue;
+            }
+            newlines += 1;
+            if newlines == lines {
+                return Ok(position + idx as u64 + 1);
+            }
+        }
+    }
+    Ok(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn tail_offset_ignores_trailing_newline() {
+        let mut data = Cursor::new(b"one\ntwo\nthree\n".to_vec());
+        assert_eq!(tail_offset(&mut data, 2).unwrap(), 4);
+        assert_eq!(tail_offset(&mut data, 1).unwrap(), 8);
+    }
+
+    #[test]
+    fn tail_offset_returns_start_when_file_is_short() {
+        let mut data = Cursor::new(b"only line".to_vec());
+        assert_eq!(tail_offset(&mut data, 5).unwrap(), 0);
+        assert_eq!(tail_offset(&mut data, 0).unwrap(), 9);
+    }
+}
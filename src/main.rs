@@ -1,29 +1,53 @@
-﻿mod config;
+mod backend;
+mod cli;
+mod config;
+mod delegate;
+mod duration;
+mod escalation;
+mod history;
+mod i18n;
+mod kill;
+mod launch;
 mod logging;
+mod logs;
+mod notify;
 mod platform;
 mod registry;
+mod retry;
+mod settings;
 mod shared_map;
 mod signal;
+mod status;
 mod supervisor;
 mod task_record;
 mod wait_mode;
 
-use crate::config::CODEX_BIN;
+use crate::backend::{Backend, BackendError};
+use crate::config::{DEFAULT_BACKEND, WAIT_EXIT_REGISTRY_ERROR};
+use crate::delegate::Delegate;
+use crate::history::HistoryError;
+use crate::kill::KillError;
+use crate::launch::LaunchOptions;
+use crate::logs::LogsError;
 use crate::registry::TaskRegistry;
+use crate::status::StatusError;
 use crate::supervisor::ProcessError;
 use crate::wait_mode::WaitError;
 use std::env;
 use std::ffi::OsString;
 use std::io::{self, Write};
-use std::process::{Command, ExitCode};
+use std::process::ExitCode;
 use thiserror::Error;
 
 fn main() -> ExitCode {
     match run() {
         Ok(code) => ExitCode::from((code & 0xFF) as u8),
         Err(err) => {
+            if supervisor::is_detached_child() {
+                supervisor::report_detached_failure(&err.to_string());
+            }
             eprintln!("{err}");
-            ExitCode::from(1)
+            ExitCode::from(err.exit_code())
         }
     }
 }
@@ -34,29 +58,63 @@ fn run() -> Result<i32, WorkerError> {
     let args: Vec<OsString> = args_iter.collect();
 
     if args.is_empty() {
-        return verify_codex();
+        return verify_backend(&Backend::load(DEFAULT_BACKEND)?);
     }
 
-    if args.len() == 1
-        && args[0]
-            .to_str()
-            .is_some_and(|cmd| cmd.eq_ignore_ascii_case("wait"))
-    {
-        wait_mode::run()?;
-        return Ok(0);
+    match args[0].to_str() {
+        Some(cmd) if cmd.eq_ignore_ascii_case("wait") => {
+            return Ok(wait_mode::run(&args[1..])?);
+        }
+        Some(cmd) if cmd.eq_ignore_ascii_case("status") => {
+            status::run(&args[1..])?;
+            return Ok(0);
+        }
+        Some(cmd) if cmd.eq_ignore_ascii_case("kill") => {
+            kill::run(&args[1..])?;
+            return Ok(0);
+        }
+        Some(cmd) if cmd.eq_ignore_ascii_case("config") => {
+            settings::run(&args[1..])?;
+            return Ok(0);
+        }
+        Some(cmd) if cmd.eq_ignore_ascii_case("history") => {
+            history::run(&args[1..])?;
+            return Ok(0);
+        }
+        Some(cmd) if cmd.eq_ignore_ascii_case("logs") => {
+            logs::run(&args[1..])?;
+            return Ok(0);
+        }
+        _ => {}
+    }
+
+    let (launch, consumed) = LaunchOptions::parse(&args)?;
+    let backend = Backend::load(launch.backend.as_deref().unwrap_or(DEFAULT_BACKEND))?;
+    if consumed == args.len() && !launch.detach {
+        return verify_backend(&backend);
+    }
+    if launch.detach {
+        let forwarded: Vec<OsString> = args[..consumed]
+            .iter()
+            .filter(|arg| arg.to_str() != Some("--detach"))
+            .chain(&args[consumed..])
+            .cloned()
+            .collect();
+        return Ok(supervisor::spawn_detached(&forwarded)?);
     }
 
     let registry = TaskRegistry::connect()?;
-    let exit_code = supervisor::execute_codex(&registry, &args)?;
+    let exit_code = supervisor::execute_codex(&registry, &backend, &launch, &args[consumed..])?;
     Ok(exit_code)
 }
 
-fn verify_codex() -> Result<i32, WorkerError> {
-    let output = Command::new(CODEX_BIN).arg("--version").output()?;
+fn verify_backend(backend: &Backend) -> Result<i32, WorkerError> {
+    let output = Delegate::resolve(backend)?.version_command().output()?;
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(WorkerError::VersionCheck(format!(
-            "Codex version check failed: {}",
+            "{} version check failed: {}",
+            backend.name,
             stderr.trim()
         )));
     }
@@ -76,6 +134,27 @@ pub enum WorkerError {
     Process(#[from] ProcessError),
     #[error("Wait mode failed: {0}")]
     Wait(#[from] WaitError),
+    #[error("Status failed: {0}")]
+    Status(#[from] StatusError),
+    #[error("Kill failed: {0}")]
+    Kill(#[from] KillError),
+    #[error("Logs failed: {0}")]
+    Logs(#[from] LogsError),
+    #[error("History failed: {0}")]
+    History(#[from] HistoryError),
     #[error("{0}")]
     VersionCheck(String),
+    #[error("{0}")]
+    Usage(#[from] cli::UsageError),
+    #[error("{0}")]
+    Backend(#[from] BackendError),
+}
+
+impl WorkerError {
+    fn exit_code(&self) -> u8 {
+        match self {
+            WorkerError::Wait(WaitError::Registry(_)) => WAIT_EXIT_REGISTRY_ERROR,
+            _ => 1,
+        }
+    }
 }
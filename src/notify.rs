@@ -0,0 +1,221 @@
+use crate::config::{CONCURRENCY_SLOTS, NOTIFY_SEGMENT_SIZE, NOTIFY_SEGMENT_SUFFIX};
+use raw_sync::Timeout;
+use raw_sync::events::{Event, EventImpl, EventInit, EventState};
+use shared_memory::{Shmem, ShmemConf, ShmemError};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+const READY: u32 = 1;
+const READY_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Debug, Error)]
+pub enum NotifyError {
+    #[error("shared memory error: {0}")]
+    Shmem(#[from] ShmemError),
+    #[error("shared event init failed: {0}")]
+    Event(String),
+    #[error("notification segment was never initialised")]
+    NotReady,
+}
+
+/// 通知段开头的固定布局，事件对象紧随其后
+#[repr(C)]
+struct Header {
+    ready: AtomicU32,
+    /// 全局并发上限，0 表示不限制；旧版本中该字段保留未用，始终为 0
+    max_concurrent: AtomicU32,
+    generation: AtomicU64,
+}
+
+/// 段末尾的并发槽位，0 表示空闲；占用时高 32 位是监督进程的 PID，
+/// 低 32 位是它启动时间的低 32 位（未知时为 0），两者一起原子更新，PID 被复用后不会误认占用者
+type Slots = [AtomicU64; CONCURRENCY_SLOTS];
+
+fn owner_word(pid: u32, start_time: Option<u64>) -> u64 {
+    (u64::from(pid) << 32) | start_time.map_or(0, |time| u64::from(time as u32))
+}
+
+fn owner_of(word: u64) -> (u32, u32) {
+    ((word >> 32) as u32, word as u32)
+}
+
+/// 跨进程的“注册表已变化”通知：变更代数加一个手动复位的共享事件
+pub struct Notifier {
+    shm: Shmem,
+    event: Box<dyn EventImpl>,
+}
+
+// SAFETY: 事件与计数器都位于进程间共享的映射中，本身就按并发访问设计；
+// 映射在 `Notifier` 存活期间保持有效
+unsafe impl Send for Notifier {}
+unsafe impl Sync for Notifier {}
+
+impl std::fmt::Debug for Notifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Notifier")
+            .field("os_id", &self.shm.get_os_id())
+            .field("generation", &self.generation())
+            .finish()
+    }
+}
+
+impl Notifier {
+    pub fn open(namespace: &str) -> Result<Self, NotifyError> {
+        let os_id = format!("{namespace}{NOTIFY_SEGMENT_SUFFIX}");
+        let conf = ShmemConf::new().os_id(&os_id).size(NOTIFY_SEGMENT_SIZE);
+        match conf.create() {
+            Ok(mut shm) => {
+                let _ = shm.set_owner(false);
+                Self::initialise(shm)
+            }
+            Err(ShmemError::MappingIdExists) => {
+                let shm = ShmemConf::new().os_id(&os_id).open()?;
+                Self::attach(shm)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn initialise(shm: Shmem) -> Result<Self, NotifyError> {
+        let header = header(&shm);
+        header.generation.store(0, Ordering::SeqCst);
+        let (event, _) = unsafe { Event::new(event_ptr(&shm), false) }
+            .map_err(|err| NotifyError::Event(err.to_string()))?;
+        header.ready.store(READY, Ordering::Release);
+        Ok(Self { shm, event })
+    }
+
+    fn attach(shm: Shmem) -> Result<Self, NotifyError> {
+        // 创建者可能还在初始化事件，稍等片刻
+        let deadline = Instant::now() + READY_TIMEOUT;
+        while header(&shm).ready.load(Ordering::Acquire) != READY {
+            if Instant::now() >= deadline {
+                return Err(NotifyError::NotReady);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        let (event, _) = unsafe { Event::from_existing(event_ptr(&shm)) }
+            .map_err(|err| NotifyError::Event(err.to_string()))?;
+        Ok(Self { shm, event })
+    }
+
+    pub fn generation(&self) -> u64 {
+        header(&self.shm).generation.load(Ordering::SeqCst)
+    }
+
+    /// 先递增代数再置位事件，等待方据此判断是否有新的变化
+    pub fn notify(&self) {
+        header(&self.shm).generation.fetch_add(1, Ordering::SeqCst);
+        let _ = self.event.set(EventState::Signaled);
+    }
+
+    pub fn max_concurrent(&self) -> u32 {
+        header(&self.shm).max_concurrent.load(Ordering::SeqCst)
+    }
+
+    pub fn set_max_concurrent(&self, limit: u32) {
+        header(&self.shm)
+            .max_concurrent
+            .store(limit.min(CONCURRENCY_SLOTS as u32), Ordering::SeqCst);
+    }
+
+    /// 在前 `max_concurrent` 个槽位中为启动于 `start_time` 的 `pid` 占一个；
+    /// `alive(pid, stamp)` 判定占用者已不存在的槽位视为空闲。
+    /// 每个槽位用比较交换占用，多个进程同时争抢时也不会超出上限
+    pub fn try_acquire_slot(
+        &self,
+        pid: u32,
+        start_time: Option<u64>,
+        alive: impl Fn(u32, u32) -> bool,
+    ) -> Option<usize> {
+        let limit = self.max_concurrent() as usize;
+        let word = owner_word(pid, start_time);
+        slots(&self.shm)
+            .iter()
+            .take(limit)
+            .enumerate()
+            .find_map(|(index, slot)| {
+                let owner = slot.load(Ordering::SeqCst);
+                let free = owner == 0 || (owner != word && !is_alive(owner, &alive));
+                (free
+                    && slot
+                        .compare_exchange(owner, word, Ordering::SeqCst, Ordering::SeqCst)
+                        .is_ok())
+                .then_some(index)
+            })
+    }
+
+    /// 只释放仍由该进程占用的槽位，并唤醒排队的进程
+    pub fn release_slot(&self, index: usize, pid: u32, start_time: Option<u64>) {
+        if let Some(slot) = slots(&self.shm).get(index) {
+            let word = owner_word(pid, start_time);
+            let _ = slot.compare_exchange(word, 0, Ordering::SeqCst, Ordering::SeqCst);
+        }
+        self.notify();
+    }
+
+    /// 占用者仍存在的槽位数
+    pub fn slots_in_use(&self, alive: impl Fn(u32, u32) -> bool) -> usize {
+        slots(&self.shm)
+            .iter()
+            .map(|slot| slot.load(Ordering::SeqCst))
+            .filter(|&owner| owner != 0 && is_alive(owner, &alive))
+            .count()
+    }
+
+    /// 等待代数离开 `seen` 或超时；返回是否观察到了变化
+    pub fn wait_for_change(&self, seen: u64, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if self.generation() != seen {
+                return true;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            // raw_sync 把纳秒直接加到当前时间上且不进位，tv_nsec 溢出时 timedwait 会立即失败；
+            // 因此只按整秒等待事件，不足一秒的余量用普通休眠补齐
+            let whole_seconds = Duration::from_secs(remaining.as_secs());
+            if whole_seconds.is_zero() {
+                thread::sleep(remaining);
+                continue;
+            }
+            if self.event.wait(Timeout::Val(whole_seconds)).is_err() {
+                // 超时；若是事件本身出错则退化为普通休眠，避免调用方空转
+                thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                return self.generation() != seen;
+            }
+            if self.generation() == seen {
+                // 事件仍停留在之前某次通知的置位状态；复位后回到循环顶部重新检查代数，
+                // 由于通知方先递增代数再置位，这里不会丢失并发的通知
+                let _ = self.event.set(EventState::Clear);
+            }
+        }
+    }
+}
+
+fn is_alive(word: u64, alive: impl Fn(u32, u32) -> bool) -> bool {
+    let (pid, stamp) = owner_of(word);
+    alive(pid, stamp)
+}
+
+fn header(shm: &Shmem) -> &Header {
+    // SAFETY: 段大小远大于 Header，且映射起始地址按页对齐
+    unsafe { &*(shm.as_ptr() as *const Header) }
+}
+
+fn slots(shm: &Shmem) -> &Slots {
+    // SAFETY: 槽位表位于段末尾，与开头的 Header 和事件对象不重叠；映射初始内容为零
+    unsafe {
+        &*(shm
+            .as_ptr()
+            .add(NOTIFY_SEGMENT_SIZE - std::mem::size_of::<Slots>()) as *const Slots)
+    }
+}
+
+fn event_ptr(shm: &Shmem) -> *mut u8 {
+    unsafe { shm.as_ptr().add(std::mem::size_of::<Header>()) }
+}
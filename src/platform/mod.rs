@@ -17,6 +17,26 @@ pub use windows::*;
 #[cfg(not(any(unix, windows)))]
 compile_error!("codex-warden platform module is not supported on this operating system");
 
+/// 进程仍然存活，并且（在登记了启动时间时）启动时间与登记时一致；
+/// PID 被系统复用给其他进程时返回 `false`。无法读取启动时间的平台上只检查存活
+pub fn same_process_alive(pid: u32, start_time: Option<u64>) -> bool {
+    if !process_alive(pid) {
+        return false;
+    }
+    match (start_time, process_start_time(pid)) {
+        (Some(expected), Some(actual)) => expected == actual,
+        _ => true,
+    }
+}
+
+/// 并发槽位的占用者是否仍在运行。槽位只能与 PID 一起原子保存启动时间的低 32 位，
+/// 因此与 `same_process_alive` 相同的复用检查只比较这部分（为 0 表示未知）；未回收的僵尸进程视为已退出
+pub fn slot_owner_alive(pid: u32, start_stamp: u32) -> bool {
+    process_running(pid)
+        && (start_stamp == 0
+            || process_start_time(pid).is_none_or(|time| time as u32 == start_stamp))
+}
+
 pub fn prepare_command(cmd: &mut Command) -> std::io::Result<()> {
     #[cfg(unix)]
     {
@@ -29,6 +49,19 @@ pub fn prepare_command(cmd: &mut Command) -> std::io::Result<()> {
     Ok(())
 }
 
+/// 让后台监督进程脱离当前终端与进程组，父进程退出后仍继续运行
+pub fn prepare_detached(cmd: &mut Command) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        unix::prepare_detached(cmd)?;
+    }
+    #[cfg(windows)]
+    {
+        windows::prepare_detached(cmd)?;
+    }
+    Ok(())
+}
+
 pub fn after_spawn(child: &std::process::Child) -> std::io::Result<ChildResources> {
     #[cfg(unix)]
     {
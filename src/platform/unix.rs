@@ -1,8 +1,12 @@
+use crate::escalation::{EscalationPolicy, StopSignal};
 use crate::logging::debug;
 use std::io;
 use std::process::Command;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+const GRACE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const STOP_CONFIRM_WINDOW: Duration = Duration::from_secs(1);
 
 /// 安全地准备子进程的执行环境
 ///
@@ -36,6 +40,22 @@ pub fn prepare_command(cmd: &mut Command) -> io::Result<()> {
     Ok(())
 }
 
+/// 为后台监督进程创建新的会话，使其不再接收终端的 SIGINT/SIGHUP
+pub fn prepare_detached(cmd: &mut Command) -> io::Result<()> {
+    use std::os::unix::process::CommandExt;
+
+    unsafe {
+        cmd.pre_exec(|| {
+            if start_new_session() < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    Ok(())
+}
+
 /// 检查进程是否存活
 ///
 /// 使用更安全的系统调用包装器
@@ -54,42 +74,246 @@ pub fn process_alive(pid: u32) -> bool {
     }
 }
 
+/// 进程的启动时间，用于识别 PID 是否已被复用
+///
+/// Linux 上取 `/proc/<pid>/stat` 的第 22 个字段（自开机以来的时钟周期数）；其他 Unix 返回 `None`
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+        parse_stat_start_time(&stat)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// 读取 `/proc/<pid>/stat` 中编号为 `field`（从 1 开始）的字段
+///
+/// 进程名可能包含空格和括号，因此从最后一个 `)` 之后开始数字段，其后第一个字段是第 3 个字段
+#[cfg(any(target_os = "linux", test))]
+fn stat_field<T: std::str::FromStr>(stat: &str, field: usize) -> Option<T> {
+    let rest = &stat[stat.rfind(')')? + 1..];
+    rest.split_whitespace()
+        .nth(field.checked_sub(3)?)?
+        .parse()
+        .ok()
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn parse_stat_start_time(stat: &str) -> Option<u64> {
+    stat_field(stat, 22)
+}
+
 /// 终止进程
 ///
-/// 首先尝试优雅地终止（SIGTERM），如果失败则强制终止（SIGKILL）
+/// 按默认升级策略依次发送信号，直到整棵进程树退出
 pub fn terminate_process(pid: u32) {
-    #[cfg(unix)]
-    {
-        let c_pid = pid as libc::pid_t;
+    stop_process(pid, &EscalationPolicy::default());
+}
 
-        // 首先检查进程是否存在
-        if !process_alive(pid) {
-            return;
+/// 按 `policy` 逐步终止进程及其整个进程树，返回最终让它退出的信号；进程早已退出时返回 `None`
+pub fn stop_process(pid: u32, policy: &EscalationPolicy) -> Option<StopSignal> {
+    escalate(pid, policy, false)
+}
+
+/// 信号处理器已经转发了策略的第一个信号：从它的宽限期开始继续升级
+pub fn continue_stop(pid: u32, policy: &EscalationPolicy) -> Option<StopSignal> {
+    escalate(pid, policy, true)
+}
+
+/// 供信号处理器调用，只发起 `killpg` / `kill` 系统调用，不分配内存也不加锁。
+/// 子进程是自己进程组的组长，组不存在时退回只发给它本身
+pub fn forward_signal(pid: u32, signal: StopSignal) {
+    let signal = raw_signal(signal);
+    unsafe {
+        if libc::killpg(pid as libc::pid_t, signal) != 0 {
+            libc::kill(pid as libc::pid_t, signal);
         }
+    }
+}
 
-        // 优雅终止
-        if unsafe_send_signal(c_pid, libc::SIGTERM).is_ok() {
-            thread::sleep(Duration::from_millis(500));
+fn escalate(pid: u32, policy: &EscalationPolicy, first_sent: bool) -> Option<StopSignal> {
+    let first = policy.steps.first().map(|step| step.signal);
+    let tree = ProcessTree::capture(pid);
+    if !tree.alive() {
+        return if first_sent { first } else { None };
+    }
+
+    let mut last = None;
+    for (index, step) in policy.steps.iter().enumerate() {
+        if !(first_sent && index == 0) {
+            tree.signal(step.signal);
+        }
+        last = Some(step.signal);
+        // 最后一步没有宽限期时也稍等片刻，确认信号是否生效
+        let grace = if step.grace.is_zero() {
+            STOP_CONFIRM_WINDOW
+        } else {
+            step.grace
+        };
+        if tree.wait_exit(grace) {
+            debug(format!("pid={pid} stopped by {}", step.signal.as_str()));
+            return last;
+        }
+    }
+    debug(format!("pid={pid} still alive after stop policy {policy}"));
+    last
+}
 
-            // 检查是否已经终止
-            if !process_alive(pid) {
-                return;
+/// 需要一起终止的进程：以 `pid` 为组长的进程组，加上已经离开该组的后代进程
+///
+/// 后代必须在发送信号之前收集，组长退出后它们会被 init 收养，父子关系随之丢失
+struct ProcessTree {
+    pid: libc::pid_t,
+    group: Option<libc::pid_t>,
+    escaped: Vec<libc::pid_t>,
+}
+
+impl ProcessTree {
+    fn capture(pid: u32) -> Self {
+        let pid = pid as libc::pid_t;
+        // 组长退出后进程组仍可能有成员；组存在期间内核不会把该 id 分配给新进程
+        let own_group = unsafe { libc::getpgrp() };
+        let group = (pid != own_group && group_exists(pid)).then_some(pid);
+        Self {
+            pid,
+            group,
+            escaped: escaped_descendants(pid),
+        }
+    }
+
+    fn alive(&self) -> bool {
+        self.group.is_some_and(group_running)
+            || process_running(self.pid as u32)
+            || self.escaped.iter().any(|&pid| process_running(pid as u32))
+    }
+
+    /// 在 `timeout` 内轮询整棵树是否都已退出
+    fn wait_exit(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        loop {
+            thread::sleep(GRACE_POLL_INTERVAL.min(timeout));
+            if !self.alive() {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
             }
         }
+    }
 
-        // 强制终止
-        if unsafe_send_signal(c_pid, libc::SIGKILL).is_ok() {
-            debug(format!("pid={} sent SIGKILL", pid));
+    fn signal(&self, signal: StopSignal) {
+        let signal = raw_signal(signal);
+        match self.group {
+            Some(group) => {
+                let _ = unsafe { libc::killpg(group, signal) };
+            }
+            None => {
+                let _ = unsafe_send_signal(self.pid, signal);
+            }
+        }
+        for &pid in &self.escaped {
+            let _ = unsafe_send_signal(pid, signal);
         }
     }
+}
 
-    #[cfg(not(unix))]
-    {
-        // 非Unix系统的实现（如果需要的话）
-        // 目前是空实现
+fn raw_signal(signal: StopSignal) -> libc::c_int {
+    match signal {
+        StopSignal::Int => libc::SIGINT,
+        StopSignal::Term => libc::SIGTERM,
+        StopSignal::Kill => libc::SIGKILL,
+    }
+}
+
+fn group_exists(group: libc::pid_t) -> bool {
+    let result = unsafe { libc::killpg(group, 0) };
+    result == 0 || get_last_errno() == libc::EPERM
+}
+
+/// 已退出但尚未被回收的僵尸进程仍能通过信号 0 的检查；监督进程被暂停时子进程会一直停留在该状态，
+/// 因此等待退出时把僵尸视为已结束
+#[cfg(target_os = "linux")]
+pub fn process_running(pid: u32) -> bool {
+    match std::fs::read_to_string(format!("/proc/{pid}/stat")) {
+        Ok(stat) => !is_zombie(&stat),
+        Err(_) => process_alive(pid),
     }
 }
 
+#[cfg(not(target_os = "linux"))]
+pub fn process_running(pid: u32) -> bool {
+    process_alive(pid)
+}
+
+/// 进程组中还有非僵尸成员
+#[cfg(target_os = "linux")]
+fn group_running(group: libc::pid_t) -> bool {
+    group_exists(group)
+        && proc_stats().any(|(_, stat)| stat_field(&stat, 5) == Some(group) && !is_zombie(&stat))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn group_running(group: libc::pid_t) -> bool {
+    group_exists(group)
+}
+
+#[cfg(any(target_os = "linux", test))]
+fn is_zombie(stat: &str) -> bool {
+    stat_field::<char>(stat, 3) == Some('Z')
+}
+
+/// `/proc` 下每个进程的 pid 与 stat 内容
+#[cfg(target_os = "linux")]
+fn proc_stats() -> impl Iterator<Item = (libc::pid_t, String)> {
+    std::fs::read_dir("/proc")
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let pid: libc::pid_t = entry.ok()?.file_name().to_str()?.parse().ok()?;
+            let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+            Some((pid, stat))
+        })
+}
+
+/// 遍历 `/proc` 找出 `root` 的全部后代中不在 `root` 进程组里的进程
+#[cfg(target_os = "linux")]
+fn escaped_descendants(root: libc::pid_t) -> Vec<libc::pid_t> {
+    let processes: Vec<(libc::pid_t, libc::pid_t, libc::pid_t)> = proc_stats()
+        .filter_map(|(pid, stat)| Some((pid, stat_field(&stat, 4)?, stat_field(&stat, 5)?)))
+        .collect();
+    escaped_from(root, &processes)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn escaped_descendants(_root: libc::pid_t) -> Vec<libc::pid_t> {
+    Vec::new()
+}
+
+/// `processes` 为 `(pid, ppid, pgrp)`；按父子关系逐层展开
+#[cfg(any(target_os = "linux", test))]
+fn escaped_from(
+    root: libc::pid_t,
+    processes: &[(libc::pid_t, libc::pid_t, libc::pid_t)],
+) -> Vec<libc::pid_t> {
+    let mut escaped = Vec::new();
+    let mut frontier = vec![root];
+    while let Some(parent) = frontier.pop() {
+        for &(pid, ppid, pgrp) in processes {
+            if ppid == parent && pid != root {
+                frontier.push(pid);
+                if pgrp != root {
+                    escaped.push(pid);
+                }
+            }
+        }
+    }
+    escaped
+}
+
 /// 安全地设置进程组ID
 ///
 /// 封装了unsafe的setpgid调用
@@ -98,6 +322,14 @@ unsafe fn set_process_group() -> libc::c_int {
     unsafe { libc::setpgid(0, 0) }
 }
 
+/// 安全地创建新会话
+///
+/// 封装了unsafe的setsid调用
+#[cfg(unix)]
+unsafe fn start_new_session() -> libc::pid_t {
+    unsafe { libc::setsid() }
+}
+
 /// 安全地设置父进程死亡信号
 ///
 /// 封装了unsafe的prctl调用
@@ -146,3 +378,53 @@ fn get_last_errno() -> libc::c_int {
         0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_start_time_after_command_name() {
+        let stat = "4242 (my (odd) cmd) S 1 4242 4242 0 -1 4194560 120 0 0 0 3 1 0 0 20 0 1 0 987654 1000 10";
+        assert_eq!(parse_stat_start_time(stat), Some(987654));
+        assert_eq!(parse_stat_start_time("4242 (truncated"), None);
+        assert_eq!(stat_field::<i32>(stat, 5), Some(4242));
+        assert!(!is_zombie(stat));
+        assert!(is_zombie("4242 (cmd) Z 1 4242 4242 0"));
+    }
+
+    #[test]
+    fn finds_descendants_outside_the_group() {
+        // 100 为组长；101 留在组内，102 自建进程组，103 是 102 的子进程，200 无关
+        let processes = [
+            (101, 100, 100),
+            (102, 101, 102),
+            (103, 102, 102),
+            (200, 1, 200),
+        ];
+        let mut escaped = escaped_from(100, &processes);
+        escaped.sort();
+        assert_eq!(escaped, vec![102, 103]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn detects_reused_pid_by_start_time() {
+        let pid = std::process::id();
+        let started = process_start_time(pid);
+        assert!(started.is_some());
+        assert!(crate::platform::same_process_alive(pid, started));
+        assert!(!crate::platform::same_process_alive(
+            pid,
+            started.map(|t| t + 1)
+        ));
+
+        // 并发槽位只保存启动时间的低 32 位
+        let stamp = started.map_or(0, |t| t as u32);
+        assert!(crate::platform::slot_owner_alive(pid, stamp));
+        assert!(!crate::platform::slot_owner_alive(
+            pid,
+            stamp.wrapping_add(1)
+        ));
+    }
+}
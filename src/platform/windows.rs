@@ -1,19 +1,25 @@
+use crate::escalation::{EscalationPolicy, StopSignal};
 use crate::logging::debug;
 use std::io;
 use std::os::windows::io::AsRawHandle;
-use windows::Win32::Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE, STILL_ACTIVE};
+use windows::Win32::Foundation::{
+    CloseHandle, FILETIME, HANDLE, INVALID_HANDLE_VALUE, STILL_ACTIVE,
+};
 use windows::Win32::System::Console::{
     CONSOLE_MODE, ENABLE_VIRTUAL_TERMINAL_PROCESSING, GetConsoleMode, GetStdHandle,
     STD_ERROR_HANDLE, STD_OUTPUT_HANDLE, SetConsoleMode,
 };
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, PROCESSENTRY32W, Process32FirstW, Process32NextW, TH32CS_SNAPPROCESS,
+};
 use windows::Win32::System::JobObjects::{
     AssignProcessToJobObject, CreateJobObjectW, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
     JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JobObjectExtendedLimitInformation,
     SetInformationJobObject,
 };
 use windows::Win32::System::Threading::{
-    GetExitCodeProcess, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE,
-    TerminateProcess, WaitForSingleObject,
+    GetExitCodeProcess, GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+    PROCESS_TERMINATE, TerminateProcess, WaitForSingleObject,
 };
 use windows::core::PCWSTR;
 
@@ -21,6 +27,15 @@ pub fn prepare_command(_cmd: &mut std::process::Command) -> io::Result<()> {
     Ok(())
 }
 
+pub fn prepare_detached(cmd: &mut std::process::Command) -> io::Result<()> {
+    use std::os::windows::process::CommandExt;
+
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+    Ok(())
+}
+
 pub fn enable_virtual_terminal_processing() -> io::Result<()> {
     unsafe {
         for kind in [STD_OUTPUT_HANDLE, STD_ERROR_HANDLE] {
@@ -57,6 +72,79 @@ pub fn process_alive(pid: u32) -> bool {
     }
 }
 
+/// Windows 上已退出的进程不会残留为僵尸，与 `process_alive` 相同
+pub fn process_running(pid: u32) -> bool {
+    process_alive(pid)
+}
+
+/// 进程的创建时间（FILETIME，100ns 为单位），用于识别 PID 是否已被复用
+pub fn process_start_time(pid: u32) -> Option<u64> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut creation = FILETIME::default();
+        let mut exit = FILETIME::default();
+        let mut kernel = FILETIME::default();
+        let mut user = FILETIME::default();
+        let ok = GetProcessTimes(handle, &mut creation, &mut exit, &mut kernel, &mut user).is_ok();
+        let _ = CloseHandle(handle);
+        ok.then(|| (u64::from(creation.dwHighDateTime) << 32) | u64::from(creation.dwLowDateTime))
+    }
+}
+
+/// Windows 没有 SIGINT/SIGTERM 式的优雅终止，策略中的各步被忽略，直接结束整个进程树
+pub fn stop_process(pid: u32, _policy: &EscalationPolicy) -> Option<StopSignal> {
+    if !process_alive(pid) {
+        return None;
+    }
+    // 先收集整棵树再结束；组长先结束，避免它继续创建子进程
+    for member in process_tree(pid) {
+        terminate_process(member);
+    }
+    Some(StopSignal::Kill)
+}
+
+/// `pid` 及其全部后代。`kill` 与扫描方不在持有 JobObject 的监督进程中运行，
+/// 需要从进程快照中自行查找；父进程的 PID 可能已被复用，只收录不早于父进程创建的子进程
+fn process_tree(pid: u32) -> Vec<u32> {
+    let mut parents = Vec::new();
+    unsafe {
+        let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else {
+            return vec![pid];
+        };
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut more = Process32FirstW(snapshot, &mut entry).is_ok();
+        while more {
+            parents.push((entry.th32ProcessID, entry.th32ParentProcessID));
+            more = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+        let _ = CloseHandle(snapshot);
+    }
+
+    let mut tree = vec![pid];
+    let mut index = 0;
+    while let Some(&parent) = tree.get(index) {
+        let parent_start = process_start_time(parent);
+        for &(child, parent_pid) in &parents {
+            let newer = process_start_time(child)
+                .zip(parent_start)
+                .is_none_or(|(child_start, parent_start)| child_start >= parent_start);
+            if parent_pid == parent && child != parent && !tree.contains(&child) && newer {
+                tree.push(child);
+            }
+        }
+        index += 1;
+    }
+    tree
+}
+
+/// Windows 没有可转发的信号，由看门狗直接结束进程
+pub fn continue_stop(pid: u32, policy: &EscalationPolicy) -> Option<StopSignal> {
+    stop_process(pid, policy)
+}
+
 pub fn terminate_process(pid: u32) {
     unsafe {
         let handle = match OpenProcess(
@@ -1,21 +1,30 @@
-use crate::config::{MAX_RECORD_AGE, SHARED_MEMORY_SIZE, SHARED_NAMESPACE};
+use crate::escalation::StopSignal;
+use crate::history;
 use crate::logging::{debug, warn};
+use crate::notify::Notifier;
+use crate::settings;
 use crate::shared_map::{SharedMapError, open_or_create};
 use crate::task_record::{TaskRecord, TaskStatus};
 use chrono::{DateTime, Duration, Utc};
 use shared_hashmap::SharedMemoryHashMap;
 use std::sync::Mutex;
+use std::thread;
+use std::time::Duration as StdDuration;
 use thiserror::Error;
+use uuid::Uuid;
 
 #[derive(Debug)]
 pub struct TaskRegistry {
     map: Mutex<SharedMemoryHashMap<String, String>>,
+    /// 通知段不可用时为 `None`，等待方退化为轮询
+    notifier: Option<Notifier>,
 }
 
 #[derive(Debug, Clone)]
 pub struct RegistryEntry {
     pub pid: u32,
-    pub key: String,
+    /// 注册表的键，与 `record.log_id` 相同
+    pub task_id: String,
     pub record: TaskRecord,
 }
 
@@ -31,6 +40,22 @@ pub enum CleanupReason {
     ProcessExited,
     Timeout,
     ManagerMissing,
+    SupervisorUnresponsive,
+    CancelledByUser,
+    WaitTimeout,
+}
+
+impl CleanupReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CleanupReason::ProcessExited => "process_exited",
+            CleanupReason::Timeout => "timeout_cleanup",
+            CleanupReason::ManagerMissing => "manager_missing",
+            CleanupReason::SupervisorUnresponsive => "supervisor_unresponsive",
+            CleanupReason::CancelledByUser => "cancelled_by_user",
+            CleanupReason::WaitTimeout => "wait_timeout",
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -53,14 +78,25 @@ impl From<shared_hashmap::Error> for RegistryError {
 
 impl TaskRegistry {
     pub fn connect() -> Result<Self, RegistryError> {
-        let map = open_or_create(SHARED_NAMESPACE, SHARED_MEMORY_SIZE)?;
+        let settings = settings::get();
+        Self::open(&settings.namespace.value, settings.shared_memory_size.value)
+    }
+
+    /// 打开（必要时创建）`namespace` 下的注册表段与通知段
+    pub fn open(namespace: &str, size: usize) -> Result<Self, RegistryError> {
+        let map = open_or_create(namespace, size)?;
+        let notifier = Notifier::open(namespace)
+            .map_err(|err| debug(format!("change notifications unavailable: {err}")))
+            .ok();
         Ok(Self {
             map: Mutex::new(map),
+            notifier,
         })
     }
 
-    pub fn register(&self, pid: u32, record: &TaskRecord) -> Result<(), RegistryError> {
-        let key = pid.to_string();
+    /// 以 `record.log_id` 作为键登记任务
+    pub fn register(&self, record: &TaskRecord) -> Result<(), RegistryError> {
+        let key = record.log_id.clone();
         let value = serde_json::to_string(record)?;
         self.with_map(|map| {
             map.try_insert(key.clone(), value)?;
@@ -70,37 +106,131 @@ impl TaskRegistry {
 
     pub fn mark_completed(
         &self,
-        pid: u32,
+        task_id: &str,
         result: Option<String>,
         exit_code: Option<i32>,
         completed_at: DateTime<Utc>,
-    ) -> Result<(), RegistryError> {
-        let key = pid.to_string();
+        stopped_by: Option<StopSignal>,
+    ) -> Result<TaskRecord, RegistryError> {
+        self.update(task_id, |mut record| {
+            // 被报告为失去响应的监督进程恢复后照常收尾，以它写入的结果为准
+            if record.cleanup_reason.as_deref()
+                == Some(CleanupReason::SupervisorUnresponsive.as_str())
+            {
+                record.cleanup_reason = None;
+            }
+            record
+                .mark_completed(result, exit_code, completed_at)
+                .with_stopped_by(stopped_by)
+        })
+    }
+
+    pub fn mark_cancelled(
+        &self,
+        task_id: &str,
+        reason: CleanupReason,
+    ) -> Result<TaskRecord, RegistryError> {
+        self.update(task_id, |record| {
+            record.with_cleanup_reason(reason.as_str())
+        })
+    }
+
+    /// 先标记为取消再终止进程，确保 `wait` 不会把它当作普通失败读走；
+    /// PID 已被复用给其他进程时只标记，不发送信号。终止成功后记录最终生效的信号
+    pub fn cancel<F>(
+        &self,
+        task_id: &str,
+        reason: CleanupReason,
+        process_alive: F,
+        terminate: &dyn Fn(&TaskRecord) -> Option<StopSignal>,
+    ) -> Result<TaskRecord, RegistryError>
+    where
+        F: Fn(u32, Option<u64>) -> bool,
+    {
+        let record = self.mark_cancelled(task_id, reason)?;
+        // 排队中的任务还没有子进程，PID 为 0，绝不能当作进程组发送信号
+        if record.pid == 0 || !process_alive(record.pid, record.child_start_time) {
+            debug(format!(
+                "pid={} no longer belongs to task {task_id}, not signalling",
+                record.pid
+            ));
+            return Ok(record);
+        }
+        match terminate(&record) {
+            Some(signal) => self
+                .update(task_id, |record| record.with_stopped_by(Some(signal)))
+                // 监督进程可能已读走并删除记录，此时返回本地副本
+                .or_else(|_| Ok(record.with_stopped_by(Some(signal)))),
+            None => Ok(record),
+        }
+    }
+
+    pub fn update(
+        &self,
+        task_id: &str,
+        f: impl FnOnce(TaskRecord) -> TaskRecord,
+    ) -> Result<TaskRecord, RegistryError> {
+        self.modify(task_id, f).inspect(|_| self.notify())
+    }
+
+    /// 监督进程的定期心跳，顺带写入最近输出时间；只有扫描方关心，因此不唤醒等待方
+    pub fn heartbeat(
+        &self,
+        task_id: &str,
+        at: DateTime<Utc>,
+        last_output_at: Option<DateTime<Utc>>,
+    ) -> Result<TaskRecord, RegistryError> {
+        self.modify(task_id, |record| {
+            let record = record.with_heartbeat(at);
+            match last_output_at {
+                Some(last) => record.with_last_output_at(last),
+                None => record,
+            }
+        })
+    }
+
+    fn modify(
+        &self,
+        task_id: &str,
+        f: impl FnOnce(TaskRecord) -> TaskRecord,
+    ) -> Result<TaskRecord, RegistryError> {
+        let key = task_id.to_owned();
         self.with_map(move |map| {
             let existing = map
                 .get(&key)
-                .ok_or_else(|| RegistryError::Map(format!("no task found for pid {pid}")))?;
+                .ok_or_else(|| RegistryError::Map(format!("no task found for id {key}")))?;
             let record: TaskRecord = serde_json::from_str(&existing)?;
-            let updated_record = record.mark_completed(result, exit_code, completed_at);
+            let updated_record = f(record);
             let updated_value = serde_json::to_string(&updated_record)?;
             let _ = map.insert(key.clone(), updated_value);
-            Ok(())
+            Ok(updated_record)
         })
     }
 
-    pub fn remove(&self, pid: u32) -> Result<Option<TaskRecord>, RegistryError> {
-        let key = pid.to_string();
-        let removed = self.with_map(|map| Ok(map.remove(&key)))?;
+    /// 按任务 id 或 PID 查找任务；PID 被复用时取最近启动的一条
+    pub fn find(&self, selector: &str) -> Result<Option<RegistryEntry>, RegistryError> {
+        let entries = self.entries()?;
+        if let Some(entry) = entries.iter().find(|entry| entry.task_id == selector) {
+            return Ok(Some(entry.clone()));
+        }
+        let Ok(pid) = selector.parse::<u32>() else {
+            return Ok(None);
+        };
+        Ok(entries
+            .into_iter()
+            .filter(|entry| entry.pid == pid)
+            .max_by_key(|entry| entry.record.started_at))
+    }
+
+    pub fn remove(&self, task_id: &str) -> Result<Option<TaskRecord>, RegistryError> {
+        let removed = self.with_map(|map| Ok(map.remove(&task_id.to_owned())))?;
+        self.notify();
         match removed {
             Some(text) => Ok(Some(serde_json::from_str(&text)?)),
             None => Ok(None),
         }
     }
 
-    pub fn remove_by_pid(&self, pid: u32) -> Result<Option<TaskRecord>, RegistryError> {
-        self.remove(pid)
-    }
-
     pub fn entries(&self) -> Result<Vec<RegistryEntry>, RegistryError> {
         let snapshot: Vec<(String, String)> = {
             let guard = self.map.lock().map_err(|_| RegistryError::Poison)?;
@@ -109,18 +239,31 @@ impl TaskRegistry {
 
         let mut entries = Vec::new();
         let mut invalid_keys = Vec::new();
+        let mut legacy = Vec::new();
 
         for (key, value) in snapshot {
-            match key.parse::<u32>() {
-                Ok(pid) => match serde_json::from_str::<TaskRecord>(&value) {
-                    Ok(record) => entries.push(RegistryEntry { pid, key, record }),
-                    Err(err) => {
-                        warn(format!("failed to parse task record pid={key}: {err}"));
-                        invalid_keys.push(key);
+            match serde_json::from_str::<TaskRecord>(&value) {
+                Ok(mut record) => match key.parse::<u32>() {
+                    // 旧版本的监督进程仍按 PID 更新运行中的记录，等它写入结果后再迁移
+                    Ok(pid) if record.status == TaskStatus::Running => {
+                        if record.pid == 0 {
+                            record.pid = pid;
+                        }
+                        entries.push(RegistryEntry {
+                            pid: record.pid,
+                            task_id: key,
+                            record,
+                        });
                     }
+                    Ok(pid) => legacy.push((key.clone(), migrate_legacy(&key, pid, record))),
+                    Err(_) => entries.push(RegistryEntry {
+                        pid: record.pid,
+                        task_id: key,
+                        record,
+                    }),
                 },
-                Err(_) => {
-                    warn(format!("detected invalid pid key: {key}"));
+                Err(err) => {
+                    warn(format!("failed to parse task record {key}: {err}"));
                     invalid_keys.push(key);
                 }
             }
@@ -129,86 +272,194 @@ impl TaskRegistry {
         if !invalid_keys.is_empty() {
             self.remove_keys(&invalid_keys)?;
         }
+        if !legacy.is_empty() {
+            entries.extend(self.rekey(legacy)?);
+        }
 
         Ok(entries)
     }
 
-    pub fn get_completed_unread_tasks(&self) -> Result<Vec<(u32, TaskRecord)>, RegistryError> {
+    pub fn get_completed_unread_tasks(&self) -> Result<Vec<RegistryEntry>, RegistryError> {
         let entries = self.entries()?;
         Ok(entries
             .into_iter()
-            .filter_map(|entry| {
-                (entry.record.status == TaskStatus::CompletedButUnread)
-                    .then_some((entry.pid, entry.record))
-            })
+            .filter(|entry| entry.record.status == TaskStatus::CompletedButUnread)
             .collect())
     }
 
+    /// 把旧版本以 PID 为键的记录改存到任务 id 之下；旧键在锁内已被他人迁移时跳过
+    fn rekey(
+        &self,
+        legacy: Vec<(String, TaskRecord)>,
+    ) -> Result<Vec<RegistryEntry>, RegistryError> {
+        self.with_map(|map| {
+            let mut migrated = Vec::new();
+            for (old_key, record) in legacy {
+                if map.remove(&old_key).is_none() {
+                    continue;
+                }
+                debug(format!(
+                    "migrated pid-keyed task {old_key} to id {}",
+                    record.log_id
+                ));
+                map.insert(record.log_id.clone(), serde_json::to_string(&record)?);
+                migrated.push(RegistryEntry {
+                    pid: record.pid,
+                    task_id: record.log_id.clone(),
+                    record,
+                });
+            }
+            Ok(migrated)
+        })
+    }
+
     pub fn sweep_stale_entries<F>(
         &self,
         now: DateTime<Utc>,
         process_alive: F,
-        terminate: &dyn Fn(u32),
+        terminate: &dyn Fn(&TaskRecord) -> Option<StopSignal>,
     ) -> Result<Vec<CleanupEvent>, RegistryError>
     where
-        F: Fn(u32) -> bool,
+        F: Fn(u32, Option<u64>) -> bool,
     {
         let entries = self.entries()?;
         let mut removals = Vec::new();
+        let mut stale = Vec::new();
         let mut events = Vec::new();
 
+        let limits = SweepLimits::from_settings();
         for entry in entries {
-            let mut reason = None;
-            if !process_alive(entry.pid) {
-                reason = Some(CleanupReason::ProcessExited);
-            } else {
-                if let Some(manager_pid) = entry
-                    .record
-                    .manager_pid
-                    .filter(|&manager_pid| manager_pid != entry.pid && !process_alive(manager_pid))
-                {
+            let (reason, stopped_by) = match classify(&entry, now, &limits, &process_alive) {
+                Verdict::Keep => continue,
+                Verdict::Clean(reason) => (reason, None),
+                Verdict::Report(reason) => {
                     debug(format!(
-                        "manager pid={manager_pid} missing, terminating Codex child pid={}",
+                        "reporting task {} as {}, leaving Codex child pid={} running",
+                        entry.task_id,
+                        reason.as_str(),
                         entry.pid
                     ));
-                    terminate(entry.pid);
-                    reason = Some(CleanupReason::ManagerMissing);
+                    (reason, None)
                 }
-                if reason.is_none() {
-                    let age = now.signed_duration_since(entry.record.started_at);
-                    if age > Duration::from_std(MAX_RECORD_AGE).unwrap_or(Duration::zero()) {
-                        debug(format!(
-                            "pid={} exceeded age {:.1}h, performing timeout cleanup",
-                            entry.pid,
-                            age.num_minutes() as f64 / 60.0
-                        ));
-                        terminate(entry.pid);
-                        reason = Some(CleanupReason::Timeout);
-                    }
+                Verdict::Terminate(reason) => {
+                    debug(format!(
+                        "terminating Codex child pid={} ({})",
+                        entry.pid,
+                        reason.as_str()
+                    ));
+                    (reason, terminate(&entry.record))
                 }
+            };
+            if entry.record.status == TaskStatus::CompletedButUnread {
+                removals.push(entry.task_id.clone());
+            } else {
+                // 运行中的记录转为已完成并保留，由匹配它的 `wait` 读取
+                stale.push((entry.task_id.clone(), reason, stopped_by));
             }
-
-            if let Some(reason) = reason {
-                removals.push(entry.key.clone());
-                events.push(CleanupEvent {
-                    _pid: entry.pid,
-                    record: entry.record.with_cleanup_reason(match reason {
-                        CleanupReason::ProcessExited => "process_exited",
-                        CleanupReason::Timeout => "timeout_cleanup",
-                        CleanupReason::ManagerMissing => "manager_missing",
-                    }),
-                    reason,
-                });
-            }
+            events.push(CleanupEvent {
+                _pid: entry.pid,
+                record: entry
+                    .record
+                    .with_cleanup_reason(reason.as_str())
+                    .with_stopped_by(stopped_by),
+                reason,
+            });
         }
 
         if !removals.is_empty() {
             self.remove_keys(&removals)?;
         }
+        if !stale.is_empty() {
+            for record in self.mark_stale(&stale)? {
+                history::append(&record);
+            }
+            self.notify();
+        }
 
         Ok(events)
     }
 
+    /// 在锁内重新读取记录，只有仍处于运行状态时才写入清理原因，避免覆盖刚写入的正常完成结果
+    fn mark_stale(
+        &self,
+        stale: &[(String, CleanupReason, Option<StopSignal>)],
+    ) -> Result<Vec<TaskRecord>, RegistryError> {
+        self.with_map(|map| {
+            let mut marked = Vec::new();
+            for (key, reason, stopped_by) in stale {
+                let Some(value) = map.get(key) else {
+                    continue;
+                };
+                let record: TaskRecord = serde_json::from_str(&value)?;
+                if !record.status.is_finished() {
+                    let updated = record
+                        .with_cleanup_reason(reason.as_str())
+                        .with_stopped_by(*stopped_by);
+                    let _ = map.insert(key.clone(), serde_json::to_string(&updated)?);
+                    marked.push(updated);
+                }
+            }
+            Ok(marked)
+        })
+    }
+
+    /// 全局并发上限，0 表示不限制；通知段不可用时无法协调槽位，同样不限制
+    pub fn max_concurrent(&self) -> u32 {
+        self.notifier.as_ref().map_or(0, Notifier::max_concurrent)
+    }
+
+    /// 写入通知段，之后所有共用该注册表的监督进程都按此上限排队
+    pub fn set_max_concurrent(&self, limit: u32) {
+        if let Some(notifier) = &self.notifier {
+            notifier.set_max_concurrent(limit);
+            self.notify();
+        }
+    }
+
+    pub fn try_acquire_slot(
+        &self,
+        pid: u32,
+        start_time: Option<u64>,
+        alive: impl Fn(u32, u32) -> bool,
+    ) -> Option<usize> {
+        self.notifier
+            .as_ref()
+            .and_then(|notifier| notifier.try_acquire_slot(pid, start_time, alive))
+    }
+
+    pub fn release_slot(&self, index: usize, pid: u32, start_time: Option<u64>) {
+        if let Some(notifier) = &self.notifier {
+            notifier.release_slot(index, pid, start_time);
+        }
+    }
+
+    pub fn slots_in_use(&self, alive: impl Fn(u32, u32) -> bool) -> usize {
+        self.notifier
+            .as_ref()
+            .map_or(0, |notifier| notifier.slots_in_use(alive))
+    }
+
+    /// 当前的变更代数；在扫描注册表之前读取，传给 `wait_for_change`
+    pub fn generation(&self) -> u64 {
+        self.notifier.as_ref().map_or(0, Notifier::generation)
+    }
+
+    /// 阻塞直到其他进程修改了注册表或超时；没有通知段时等同于休眠
+    pub fn wait_for_change(&self, seen: u64, timeout: StdDuration) {
+        match &self.notifier {
+            Some(notifier) => {
+                notifier.wait_for_change(seen, timeout);
+            }
+            None => thread::sleep(timeout),
+        }
+    }
+
+    fn notify(&self) {
+        if let Some(notifier) = &self.notifier {
+            notifier.notify();
+        }
+    }
+
     fn remove_keys(&self, keys: &[String]) -> Result<(), RegistryError> {
         if keys.is_empty() {
             return Ok(());
@@ -229,3 +480,302 @@ impl TaskRegistry {
         f(&mut guard)
     }
 }
+
+/// 旧版本以 PID 为键且 `log_id` 同为 PID；补齐 `pid` 并换上新的任务 id
+fn migrate_legacy(key: &str, pid: u32, mut record: TaskRecord) -> TaskRecord {
+    if record.pid == 0 {
+        record.pid = pid;
+    }
+    if record.log_id.is_empty() || record.log_id == key {
+        record.log_id = new_task_id();
+    }
+    record
+}
+
+pub fn new_task_id() -> String {
+    Uuid::new_v4().to_string()
+}
+
+/// 扫描使用的时限，来自配置
+struct SweepLimits {
+    max_record_age: Duration,
+    heartbeat_timeout: Duration,
+}
+
+impl SweepLimits {
+    fn from_settings() -> Self {
+        let settings = settings::get();
+        Self {
+            max_record_age: Duration::from_std(settings.max_record_age.value)
+                .unwrap_or(Duration::zero()),
+            heartbeat_timeout: Duration::from_std(settings.heartbeat_timeout.value)
+                .unwrap_or(Duration::MAX),
+        }
+    }
+
+    fn is_expired(&self, record: &TaskRecord, now: DateTime<Utc>) -> bool {
+        now.signed_duration_since(record.started_at) > self.max_record_age
+    }
+
+    /// 没有心跳字段的旧记录不参与判断
+    fn heartbeat_stale(&self, record: &TaskRecord, now: DateTime<Utc>) -> bool {
+        record
+            .heartbeat_at
+            .is_some_and(|at| now.signed_duration_since(at) > self.heartbeat_timeout)
+    }
+}
+
+/// 扫描对单条记录的判定
+#[derive(Debug, PartialEq, Eq)]
+enum Verdict {
+    Keep,
+    /// 只标记清理原因，没有需要终止的进程
+    Clean(CleanupReason),
+    /// 先终止子进程再标记
+    Terminate(CleanupReason),
+    /// 只标记并报告，子进程保持运行
+    Report(CleanupReason),
+}
+
+fn classify<F>(
+    entry: &RegistryEntry,
+    now: DateTime<Utc>,
+    limits: &SweepLimits,
+    process_alive: F,
+) -> Verdict
+where
+    F: Fn(u32, Option<u64>) -> bool,
+{
+    let record = &entry.record;
+    let manager_alive = || {
+        record.manager_pid.is_some_and(|manager_pid| {
+            manager_pid != entry.pid && process_alive(manager_pid, record.manager_start_time)
+        })
+    };
+    match record.status {
+        // 已完成的记录等待 `wait` 读取，只做超龄清理，避免覆盖其结果
+        TaskStatus::CompletedButUnread if limits.is_expired(record, now) => {
+            Verdict::Clean(CleanupReason::Timeout)
+        }
+        TaskStatus::CompletedButUnread => Verdict::Keep,
+        // 尚无子进程可终止，只需判断排队的监督进程是否还在
+        TaskStatus::Queued if !manager_alive() => Verdict::Clean(CleanupReason::ManagerMissing),
+        TaskStatus::Queued if limits.heartbeat_stale(record, now) => {
+            Verdict::Clean(CleanupReason::SupervisorUnresponsive)
+        }
+        TaskStatus::Queued if limits.is_expired(record, now) => {
+            Verdict::Clean(CleanupReason::Timeout)
+        }
+        TaskStatus::Queued => Verdict::Keep,
+        // 包括 PID 已被复用的情况：原进程已不存在，不能向新进程发送信号。
+        // 管理进程仍在时由它写入退出码和结果，避免抢先标记为 process_exited
+        TaskStatus::Running if !process_alive(entry.pid, record.child_start_time) => {
+            if !manager_alive() {
+                Verdict::Clean(CleanupReason::ProcessExited)
+            } else if limits.heartbeat_stale(record, now) {
+                Verdict::Clean(CleanupReason::SupervisorUnresponsive)
+            } else if limits.is_expired(record, now) {
+                Verdict::Clean(CleanupReason::Timeout)
+            } else {
+                Verdict::Keep
+            }
+        }
+        // 监督进程被暂停、死锁或换出时仍然存在但不再写心跳：只报告，不终止子进程，
+        // 它恢复后仍会照常收尾；只有管理进程确实不在时才终止
+        TaskStatus::Running => {
+            let manager_missing = record.manager_pid.is_some_and(|manager_pid| {
+                manager_pid != entry.pid && !process_alive(manager_pid, record.manager_start_time)
+            });
+            if manager_missing {
+                Verdict::Terminate(CleanupReason::ManagerMissing)
+            } else if limits.heartbeat_stale(record, now) {
+                Verdict::Report(CleanupReason::SupervisorUnresponsive)
+            } else if limits.is_expired(record, now) {
+                Verdict::Terminate(CleanupReason::Timeout)
+            } else {
+                Verdict::Keep
+            }
+        }
+    }
+}
+
+/// 排队中的任务按入队时间（`started_at`）先后排列，返回 `task_id` 的位置，从 1 开始
+pub fn queue_position(entries: &[RegistryEntry], task_id: &str) -> Option<usize> {
+    let mut queued: Vec<&RegistryEntry> = entries
+        .iter()
+        .filter(|entry| entry.record.status == TaskStatus::Queued)
+        .collect();
+    queued
+        .sort_by(|a, b| (a.record.started_at, &a.task_id).cmp(&(b.record.started_at, &b.task_id)));
+    queued
+        .iter()
+        .position(|entry| entry.task_id == task_id)
+        .map(|index| index + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::NOTIFY_SEGMENT_SUFFIX;
+
+    #[test]
+    fn migrates_pid_keyed_records() {
+        let legacy = TaskRecord::new(
+            0,
+            Utc::now(),
+            "4242".to_owned(),
+            "/tmp/4242.log".to_owned(),
+            None,
+        );
+        let migrated = migrate_legacy("4242", 4242, legacy.clone());
+        assert_eq!(migrated.pid, 4242);
+        assert!(Uuid::parse_str(&migrated.log_id).is_ok());
+        assert_eq!(migrated.log_path, "/tmp/4242.log");
+
+        let mut named = legacy;
+        named.log_id = "nightly-lint".to_owned();
+        assert_eq!(migrate_legacy("4242", 4242, named).log_id, "nightly-lint");
+    }
+
+    #[test]
+    fn orders_queue_by_enqueue_time() {
+        let now = Utc::now();
+        let entry = |task_id: &str, minutes_ago: i64, status: TaskStatus| {
+            let mut record = TaskRecord::new(
+                0,
+                now - Duration::minutes(minutes_ago),
+                task_id.to_owned(),
+                String::new(),
+                None,
+            );
+            record.status = status;
+            RegistryEntry {
+                pid: 0,
+                task_id: task_id.to_owned(),
+                record,
+            }
+        };
+        let entries = [
+            entry("late", 1, TaskStatus::Queued),
+            entry("running", 9, TaskStatus::Running),
+            entry("early", 5, TaskStatus::Queued),
+        ];
+        assert_eq!(queue_position(&entries, "early"), Some(1));
+        assert_eq!(queue_position(&entries, "late"), Some(2));
+        assert_eq!(queue_position(&entries, "running"), None);
+    }
+
+    #[test]
+    fn classifies_supervisor_heartbeats() {
+        let now = Utc::now();
+        let limits = SweepLimits {
+            max_record_age: Duration::hours(12),
+            heartbeat_timeout: Duration::minutes(2),
+        };
+        let entry = |status: TaskStatus, heartbeat_secs_ago: Option<i64>| {
+            let mut record = TaskRecord::new(
+                100,
+                now - Duration::minutes(10),
+                "task".to_owned(),
+                String::new(),
+                Some(200),
+            );
+            record.status = status;
+            record.heartbeat_at = heartbeat_secs_ago.map(|secs| now - Duration::seconds(secs));
+            RegistryEntry {
+                pid: 100,
+                task_id: "task".to_owned(),
+                record,
+            }
+        };
+        let all_alive = |_: u32, _: Option<u64>| true;
+        let child_exited = |pid: u32, _: Option<u64>| pid != 100;
+        let manager_gone = |pid: u32, _: Option<u64>| pid != 200;
+
+        // 恰好达到超时不算过期；没有心跳字段的旧记录只看管理进程
+        let running = |secs| entry(TaskStatus::Running, secs);
+        assert_eq!(
+            classify(&running(Some(120)), now, &limits, all_alive),
+            Verdict::Keep
+        );
+        assert_eq!(
+            classify(&running(Some(121)), now, &limits, all_alive),
+            Verdict::Report(CleanupReason::SupervisorUnresponsive)
+        );
+        assert_eq!(
+            classify(&running(None), now, &limits, all_alive),
+            Verdict::Keep
+        );
+        assert_eq!(
+            classify(&running(Some(5)), now, &limits, manager_gone),
+            Verdict::Terminate(CleanupReason::ManagerMissing)
+        );
+
+        // 子进程已退出而监督进程还在：心跳新鲜时等它写结果，过期时由扫描方收尾
+        assert_eq!(
+            classify(&running(Some(5)), now, &limits, child_exited),
+            Verdict::Keep
+        );
+        assert_eq!(
+            classify(&running(Some(300)), now, &limits, child_exited),
+            Verdict::Clean(CleanupReason::SupervisorUnresponsive)
+        );
+
+        let queued = |secs| entry(TaskStatus::Queued, secs);
+        assert_eq!(
+            classify(&queued(Some(5)), now, &limits, all_alive),
+            Verdict::Keep
+        );
+        assert_eq!(
+            classify(&queued(None), now, &limits, all_alive),
+            Verdict::Keep
+        );
+        assert_eq!(
+            classify(&queued(Some(300)), now, &limits, all_alive),
+            Verdict::Clean(CleanupReason::SupervisorUnresponsive)
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn cancel_returns_final_record_after_wait_reads_it() {
+        let namespace = format!("codex_warden_test_cancel_{}", std::process::id());
+        let registry = TaskRegistry::open(&namespace, 64 * 1024).unwrap();
+        let record = TaskRecord::new(
+            4242,
+            Utc::now(),
+            "task-kill".to_owned(),
+            "/tmp/task-kill.log".to_owned(),
+            Some(4241),
+        );
+        registry.register(&record).unwrap();
+
+        // `wait` 在宽限期内读走并删除了已标记为取消的记录
+        let cancelled = registry.cancel(
+            "task-kill",
+            CleanupReason::CancelledByUser,
+            |_, _| true,
+            &|_| {
+                registry.remove("task-kill").unwrap();
+                Some(StopSignal::Term)
+            },
+        );
+        // 监督进程之后写不进完成结果，也就不会写历史，只能由 `kill` 写入它拿到的记录
+        let completed = registry.mark_completed("task-kill", None, None, Utc::now(), None);
+        for segment in [
+            namespace.clone(),
+            format!("{namespace}{NOTIFY_SEGMENT_SUFFIX}"),
+        ] {
+            let _ = std::fs::remove_file(format!("/dev/shm/{segment}"));
+        }
+
+        let cancelled = cancelled.unwrap();
+        assert!(completed.is_err());
+        assert_eq!(cancelled.status, TaskStatus::CompletedButUnread);
+        assert_eq!(
+            cancelled.cleanup_reason.as_deref(),
+            Some(CleanupReason::CancelledByUser.as_str())
+        );
+        assert_eq!(cancelled.stopped_by.as_deref(), Some("SIGTERM"));
+    }
+}
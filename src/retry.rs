@@ -0,0 +1,121 @@
+use crate::cli::UsageError;
+use crate::config::{RETRY_BACKOFF_DEFAULT, RETRY_BACKOFF_MAX};
+use regex::Regex;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// `--retry-on` 指定的重试条件
+#[derive(Debug, Clone)]
+pub enum RetryCondition {
+    /// `exit:1,2`：退出码属于列表之一
+    Exit(Vec<i32>),
+    /// `log-pattern:<regex>`：该次尝试的日志中有一行匹配
+    LogPattern(Regex),
+}
+
+impl FromStr for RetryCondition {
+    type Err = UsageError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |reason: String| UsageError(format!("invalid retry condition {value}: {reason}"));
+        match value.split_once(':') {
+            Some(("exit", codes)) => codes
+                .split(',')
+                .map(|code| code.trim().parse::<i32>())
+                .collect::<Result<Vec<_>, _>>()
+                .map(RetryCondition::Exit)
+                .map_err(|err| invalid(err.to_string())),
+            Some(("log-pattern", pattern)) => Regex::new(pattern)
+                .map(RetryCondition::LogPattern)
+                .map_err(|err| invalid(err.to_string())),
+            _ => Err(invalid(
+                "expected exit:<codes> or log-pattern:<regex>".to_owned(),
+            )),
+        }
+    }
+}
+
+impl RetryCondition {
+    fn matches(&self, exit_code: Option<i32>, log_path: &Path) -> bool {
+        match self {
+            RetryCondition::Exit(codes) => exit_code.is_some_and(|code| codes.contains(&code)),
+            RetryCondition::LogPattern(pattern) => File::open(log_path).is_ok_and(|file| {
+                BufReader::new(file)
+                    .split(b'\n')
+                    .map_while(Result::ok)
+                    .any(|line| pattern.is_match(&String::from_utf8_lossy(&line)))
+            }),
+        }
+    }
+}
+
+/// 失败后自动重新运行的策略；`retries` 为 0 时不重试
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub retries: u32,
+    /// 为空时任何失败都会重试，否则满足其中之一即可
+    pub conditions: Vec<RetryCondition>,
+    /// 第一次重试前的等待时间，之后每次翻倍
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: 0,
+            conditions: Vec::new(),
+            backoff: RETRY_BACKOFF_DEFAULT,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// 第 `attempt` 次尝试（从 1 开始）失败后、下一次尝试前的等待时间
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.backoff.saturating_mul(factor).min(RETRY_BACKOFF_MAX)
+    }
+
+    /// 失败的尝试是否值得重试
+    pub fn matches(&self, exit_code: Option<i32>, log_path: &Path) -> bool {
+        self.conditions.is_empty()
+            || self
+                .conditions
+                .iter()
+                .any(|condition| condition.matches(exit_code, log_path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_conditions_and_doubles_backoff() {
+        let exit: RetryCondition = "exit:1, 2".parse().unwrap();
+        assert!(matches!(exit, RetryCondition::Exit(ref codes) if codes == &[1, 2]));
+        assert!(exit.matches(Some(2), Path::new("/nonexistent")));
+        assert!(!exit.matches(None, Path::new("/nonexistent")));
+        assert!(
+            "log-pattern:rate limit|429"
+                .parse::<RetryCondition>()
+                .is_ok()
+        );
+        assert!("log-pattern:(".parse::<RetryCondition>().is_err());
+        assert!("exit:x".parse::<RetryCondition>().is_err());
+        assert!("signal:9".parse::<RetryCondition>().is_err());
+
+        let policy = RetryPolicy {
+            retries: 20,
+            conditions: Vec::new(),
+            backoff: Duration::from_secs(10),
+        };
+        assert_eq!(policy.delay(1), Duration::from_secs(10));
+        assert_eq!(policy.delay(3), Duration::from_secs(40));
+        assert_eq!(policy.delay(20), RETRY_BACKOFF_MAX);
+    }
+}
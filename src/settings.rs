@@ -0,0 +1,629 @@
+use crate::backend::ResultExtractor;
+use crate::cli::{self, UsageError};
+use crate::config::{
+    CODEX_BIN_ENV, CONCURRENCY_SLOTS, CONFIG_DIR_NAME, CONFIG_FILE_NAME, DEBUG_ENV,
+    HEARTBEAT_TIMEOUT, HEARTBEAT_TIMEOUT_ENV, LANG_ENV, LEGACY_DEBUG_ENV, LEGACY_WAIT_INTERVAL_ENV,
+    LOCALE_ENVS, MAX_CONCURRENT_ENV, MAX_RECORD_AGE, MAX_WAIT_DURATION, OUTPUT_MODE_ENV,
+    PROJECT_CONFIG_FILE_NAME, SHARED_MEMORY_SIZE, SHARED_NAMESPACE, WAIT_INTERVAL_DEFAULT,
+    WAIT_INTERVAL_ENV,
+};
+use crate::duration::{format_duration, parse_duration};
+use crate::escalation::EscalationPolicy;
+use crate::i18n::Lang;
+use crate::launch::OutputMode;
+use crate::logging::warn;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+const SOURCE_COLUMN_MAX: usize = 48;
+
+/// 单个配置文件中可设置的键，缺省的键由更低优先级的层决定
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FileSettings {
+    pub namespace: Option<String>,
+    pub shared_memory_size: Option<usize>,
+    pub max_record_age: Option<DurationValue>,
+    pub max_wait_duration: Option<DurationValue>,
+    pub wait_interval: Option<DurationValue>,
+    pub heartbeat_timeout: Option<DurationValue>,
+    pub max_concurrent: Option<u32>,
+    pub debug: Option<bool>,
+    pub codex_bin: Option<String>,
+    pub output: Option<String>,
+    pub lang: Option<String>,
+    pub backends: BTreeMap<String, BackendSettings>,
+}
+
+/// `[backends.<name>]` 表，覆盖内置后端的字段或定义新的后端
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackendSettings {
+    pub binary: Option<String>,
+    pub args: Option<Vec<String>>,
+    pub version_args: Option<Vec<String>>,
+    pub result: Option<ResultExtractor>,
+    pub stop_policy: Option<EscalationPolicy>,
+}
+
+impl BackendSettings {
+    fn merge(&mut self, other: &BackendSettings) {
+        if other.binary.is_some() {
+            self.binary = other.binary.clone();
+        }
+        if other.args.is_some() {
+            self.args = other.args.clone();
+        }
+        if other.version_args.is_some() {
+            self.version_args = other.version_args.clone();
+        }
+        if other.result.is_some() {
+            self.result = other.result.clone();
+        }
+        if other.stop_policy.is_some() {
+            self.stop_policy = other.stop_policy.clone();
+        }
+    }
+}
+
+/// 时长既可以写成秒数，也可以写成 `"12h"` 这样的字符串
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum DurationValue {
+    Seconds(u64),
+    Text(String),
+}
+
+impl DurationValue {
+    fn to_duration(&self) -> Result<Duration, String> {
+        match self {
+            DurationValue::Seconds(seconds) => Ok(Duration::from_secs(*seconds)),
+            DurationValue::Text(text) => parse_duration(text),
+        }
+    }
+}
+
+/// 某个配置值的来源
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Default,
+    UserFile(PathBuf),
+    ProjectFile(PathBuf),
+    Env(&'static str),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::UserFile(path) => write!(f, "user config {}", path.display()),
+            Source::ProjectFile(path) => write!(f, "project config {}", path.display()),
+            Source::Env(var) => write!(f, "env {var}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+impl<T> Setting<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value,
+            source: Source::Default,
+        }
+    }
+
+    fn set(&mut self, value: T, source: Source) {
+        self.value = value;
+        self.source = source;
+    }
+}
+
+/// 一个已读取的配置文件层
+pub struct Layer {
+    source: Source,
+    settings: FileSettings,
+}
+
+/// 合并默认值、用户配置、项目配置与环境变量后的有效配置
+#[derive(Debug)]
+pub struct Settings {
+    pub namespace: Setting<String>,
+    pub shared_memory_size: Setting<usize>,
+    pub max_record_age: Setting<Duration>,
+    pub max_wait_duration: Setting<Duration>,
+    pub wait_interval: Setting<Duration>,
+    pub heartbeat_timeout: Setting<Duration>,
+    /// 未设置时沿用通知段中已有的上限；0 表示不限制
+    pub max_concurrent: Setting<Option<u32>>,
+    pub debug: Setting<bool>,
+    pub codex_bin: Setting<Option<String>>,
+    pub output: Setting<OutputMode>,
+    pub lang: Setting<Lang>,
+    pub backends: BTreeMap<String, Setting<BackendSettings>>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            namespace: Setting::new(SHARED_NAMESPACE.to_owned()),
+            shared_memory_size: Setting::new(SHARED_MEMORY_SIZE),
+            max_record_age: Setting::new(MAX_RECORD_AGE),
+            max_wait_duration: Setting::new(MAX_WAIT_DURATION),
+            wait_interval: Setting::new(WAIT_INTERVAL_DEFAULT),
+            heartbeat_timeout: Setting::new(HEARTBEAT_TIMEOUT),
+            max_concurrent: Setting::new(None),
+            debug: Setting::new(false),
+            codex_bin: Setting::new(None),
+            output: Setting::new(OutputMode::default()),
+            lang: Setting::new(Lang::default()),
+            backends: BTreeMap::new(),
+        }
+    }
+}
+
+impl Settings {
+    /// 按从低到高的优先级依次应用各层，最后应用环境变量
+    pub fn resolve(layers: &[Layer], env: impl Fn(&str) -> Option<String>) -> Self {
+        let mut settings = Settings::default();
+        for layer in layers {
+            settings.apply_file(layer);
+        }
+        settings.apply_env(env);
+        settings
+    }
+
+    fn apply_file(&mut self, layer: &Layer) {
+        let file = &layer.settings;
+        let source = &layer.source;
+        if let Some(namespace) = &file.namespace {
+            self.namespace.set(namespace.clone(), source.clone());
+        }
+        if let Some(size) = file.shared_memory_size {
+            self.shared_memory_size.set(size, source.clone());
+        }
+        let durations = [
+            (
+                "max_record_age",
+                &file.max_record_age,
+                &mut self.max_record_age,
+            ),
+            (
+                "max_wait_duration",
+                &file.max_wait_duration,
+                &mut self.max_wait_duration,
+            ),
+            (
+                "wait_interval",
+                &file.wait_interval,
+                &mut self.wait_interval,
+            ),
+            (
+                "heartbeat_timeout",
+                &file.heartbeat_timeout,
+                &mut self.heartbeat_timeout,
+            ),
+        ];
+        for (key, value, target) in durations {
+            match value.as_ref().map(DurationValue::to_duration) {
+                Some(Ok(duration)) => target.set(duration, source.clone()),
+                Some(Err(err)) => warn(format!("{source}: invalid {key}: {err}")),
+                None => {}
+            }
+        }
+        if let Some(limit) = file.max_concurrent {
+            self.max_concurrent
+                .set(Some(clamp_concurrency(limit, source)), source.clone());
+        }
+        if let Some(debug) = file.debug {
+            self.debug.set(debug, source.clone());
+        }
+        if let Some(codex_bin) = &file.codex_bin {
+            self.codex_bin.set(Some(codex_bin.clone()), source.clone());
+        }
+        if let Some(output) = &file.output {
+            match output.parse() {
+                Ok(mode) => self.output.set(mode, source.clone()),
+                Err(err) => warn(format!("{source}: {err}")),
+            }
+        }
+        if let Some(lang) = &file.lang {
+            match lang.parse() {
+                Ok(lang) => self.lang.set(lang, source.clone()),
+                Err(err) => warn(format!("{source}: {err}")),
+            }
+        }
+        for (name, backend) in &file.backends {
+            let entry = self
+                .backends
+                .entry(name.clone())
+                .or_insert_with(|| Setting::new(BackendSettings::default()));
+            entry.value.merge(backend);
+            entry.source = source.clone();
+        }
+    }
+
+    fn apply_env(&mut self, env: impl Fn(&str) -> Option<String>) {
+        if let Some((interval, var)) = read_env_interval(&env, WAIT_INTERVAL_ENV)
+            .or_else(|| read_env_interval(&env, LEGACY_WAIT_INTERVAL_ENV))
+        {
+            self.wait_interval.set(interval, Source::Env(var));
+        }
+        if let Some((timeout, var)) = read_env_interval(&env, HEARTBEAT_TIMEOUT_ENV) {
+            self.heartbeat_timeout.set(timeout, Source::Env(var));
+        }
+        if let Some(raw) = env(MAX_CONCURRENT_ENV) {
+            let source = Source::Env(MAX_CONCURRENT_ENV);
+            match raw.parse() {
+                Ok(limit) => self
+                    .max_concurrent
+                    .set(Some(clamp_concurrency(limit, &source)), source),
+                Err(_) => warn(format!(
+                    "environment variable {MAX_CONCURRENT_ENV} invalid, ignoring {raw}"
+                )),
+            }
+        }
+        if let Some((debug, var)) =
+            read_env_bool(&env, DEBUG_ENV).or_else(|| read_env_bool(&env, LEGACY_DEBUG_ENV))
+        {
+            self.debug.set(debug, Source::Env(var));
+        }
+        if let Some(codex_bin) = env(CODEX_BIN_ENV).filter(|value| !value.is_empty()) {
+            self.codex_bin
+                .set(Some(codex_bin), Source::Env(CODEX_BIN_ENV));
+        }
+        if let Some(raw) = env(OUTPUT_MODE_ENV) {
+            match raw.parse() {
+                Ok(mode) => self.output.set(mode, Source::Env(OUTPUT_MODE_ENV)),
+                Err(_) => warn(format!(
+                    "environment variable {OUTPUT_MODE_ENV} invalid, using {}",
+                    output_name(self.output.value)
+                )),
+            }
+        }
+        if let Some(raw) = env(LANG_ENV) {
+            match raw.parse() {
+                Ok(lang) => self.lang.set(lang, Source::Env(LANG_ENV)),
+                Err(err) => warn(format!("environment variable {LANG_ENV}: {err}")),
+            }
+        }
+        // 系统 locale 只在没有显式配置语言时生效
+        if self.lang.source == Source::Default
+            && let Some((raw, var)) = LOCALE_ENVS.iter().find_map(|var| {
+                env(var)
+                    .filter(|value| !value.is_empty())
+                    .map(|v| (v, *var))
+            })
+        {
+            self.lang.set(Lang::from_locale(&raw), Source::Env(var));
+        }
+    }
+}
+
+fn read_env_interval(
+    env: &impl Fn(&str) -> Option<String>,
+    var: &'static str,
+) -> Option<(Duration, &'static str)> {
+    let raw = env(var)?;
+    match parse_duration(&raw) {
+        Ok(interval) if !interval.is_zero() => Some((interval, var)),
+        _ => {
+            warn(format!(
+                "environment variable {var} invalid, ignoring {raw}"
+            ));
+            None
+        }
+    }
+}
+
+/// 槽位表大小固定，超出部分无法协调
+fn clamp_concurrency(limit: u32, source: &Source) -> u32 {
+    if limit as usize > CONCURRENCY_SLOTS {
+        warn(format!(
+            "{source}: max_concurrent {limit} exceeds {CONCURRENCY_SLOTS}, using {CONCURRENCY_SLOTS}"
+        ));
+        return CONCURRENCY_SLOTS as u32;
+    }
+    limit
+}
+
+fn read_env_bool(
+    env: &impl Fn(&str) -> Option<String>,
+    var: &'static str,
+) -> Option<(bool, &'static str)> {
+    env(var).map(|value| (value == "1" || value.eq_ignore_ascii_case("true"), var))
+}
+
+pub fn get() -> &'static Settings {
+    SETTINGS.get_or_init(|| Settings::resolve(&discover_layers(), |var| std::env::var(var).ok()))
+}
+
+/// 用户配置在前，当前目录向上最近的项目配置在后
+fn discover_layers() -> Vec<Layer> {
+    let mut layers = Vec::new();
+    if let Some(path) = user_config_path()
+        && let Some(settings) = load_file(&path)
+    {
+        layers.push(Layer {
+            source: Source::UserFile(path),
+            settings,
+        });
+    }
+    if let Some(path) = project_config_path()
+        && let Some(settings) = load_file(&path)
+    {
+        layers.push(Layer {
+            source: Source::ProjectFile(path),
+            settings,
+        });
+    }
+    layers
+}
+
+/// `$XDG_CONFIG_HOME/codex-warden/config.toml`，未设置时使用 `~/.config`；
+/// Windows 上位于 `%APPDATA%\codex-warden\config.toml`
+pub fn user_config_path() -> Option<PathBuf> {
+    config_home().map(|dir| dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME))
+}
+
+/// 从当前目录逐级向上查找 `.codex-warden.toml`
+pub fn project_config_path() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+fn config_home() -> Option<PathBuf> {
+    #[cfg(windows)]
+    {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    }
+    #[cfg(not(windows))]
+    {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .filter(|value| !value.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    }
+}
+
+fn load_file(path: &Path) -> Option<FileSettings> {
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(err) => {
+            warn(format!("cannot read config {}: {err}", path.display()));
+            return None;
+        }
+    };
+    match toml::from_str(&text) {
+        Ok(settings) => Some(settings),
+        Err(err) => {
+            warn(format!("invalid config {}: {err}", path.display()));
+            None
+        }
+    }
+}
+
+fn output_name(mode: OutputMode) -> &'static str {
+    match mode {
+        OutputMode::Mirror => "mirror",
+        OutputMode::Quiet => "quiet",
+        OutputMode::Summary => "summary",
+    }
+}
+
+/// `codex-warden config show`：打印有效配置及每个值的来源
+pub fn run(args: &[OsString]) -> Result<(), UsageError> {
+    let args = cli::to_strings(args)?;
+    match args.first().map(String::as_str) {
+        Some("show") if args.len() == 1 => {
+            print!("{}", render(get()));
+            Ok(())
+        }
+        Some("show") => Err(cli::unknown_argument(&args[1])),
+        Some(other) => Err(cli::unknown_argument(other)),
+        None => Err(UsageError("usage: codex-warden config show".to_owned())),
+    }
+}
+
+fn render(settings: &Settings) -> String {
+    let mut rows: Vec<(String, String, &Source)> = vec![
+        (
+            "namespace".to_owned(),
+            format!("{:?}", settings.namespace.value),
+            &settings.namespace.source,
+        ),
+        (
+            "shared_memory_size".to_owned(),
+            settings.shared_memory_size.value.to_string(),
+            &settings.shared_memory_size.source,
+        ),
+        (
+            "max_record_age".to_owned(),
+            format!("{:?}", format_duration(settings.max_record_age.value)),
+            &settings.max_record_age.source,
+        ),
+        (
+            "max_wait_duration".to_owned(),
+            format!("{:?}", format_duration(settings.max_wait_duration.value)),
+            &settings.max_wait_duration.source,
+        ),
+        (
+            "wait_interval".to_owned(),
+            format!("{:?}", format_duration(settings.wait_interval.value)),
+            &settings.wait_interval.source,
+        ),
+        (
+            "max_concurrent".to_owned(),
+            settings
+                .max_concurrent
+                .value
+                .map_or_else(|| "(unset)".to_owned(), |limit| limit.to_string()),
+            &settings.max_concurrent.source,
+        ),
+        (
+            "heartbeat_timeout".to_owned(),
+            format!("{:?}", format_duration(settings.heartbeat_timeout.value)),
+            &settings.heartbeat_timeout.source,
+        ),
+        (
+            "debug".to_owned(),
+            settings.debug.value.to_string(),
+            &settings.debug.source,
+        ),
+        (
+            "codex_bin".to_owned(),
+            settings
+                .codex_bin
+                .value
+                .as_ref()
+                .map_or_else(|| "(unset)".to_owned(), |bin| format!("{bin:?}")),
+            &settings.codex_bin.source,
+        ),
+        (
+            "output".to_owned(),
+            format!("{:?}", output_name(settings.output.value)),
+            &settings.output.source,
+        ),
+        (
+            "lang".to_owned(),
+            format!("{:?}", settings.lang.value.code()),
+            &settings.lang.source,
+        ),
+    ];
+    for (name, backend) in &settings.backends {
+        let value = &backend.value;
+        let mut fields = Vec::new();
+        if let Some(binary) = &value.binary {
+            fields.push(format!("binary = {binary:?}"));
+        }
+        if let Some(args) = &value.args {
+            fields.push(format!("args = {args:?}"));
+        }
+        if let Some(version_args) = &value.version_args {
+            fields.push(format!("version_args = {version_args:?}"));
+        }
+        if let Some(result) = &value.result {
+            fields.push(format!("result = {:?}", result.to_string()));
+        }
+        if let Some(policy) = &value.stop_policy {
+            fields.push(format!("stop_policy = {:?}", policy.to_string()));
+        }
+        rows.push((
+            format!("backends.{name}"),
+            format!("{{ {} }}", fields.join(", ")),
+            &backend.source,
+        ));
+    }
+
+    // 过长的行（通常是后端表）不参与对齐，避免把来源注释推得过远
+    let width = rows
+        .iter()
+        .map(|(key, value, _)| key.len() + value.len() + 3)
+        .filter(|len| *len <= SOURCE_COLUMN_MAX)
+        .max()
+        .unwrap_or(0);
+    let mut output = String::new();
+    for (key, value, source) in rows {
+        let line = format!("{key} = {value}");
+        output.push_str(&format!("{line:<width$}  # {source}\n"));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn env_from(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let map: HashMap<String, String> = pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_owned(), (*v).to_owned()))
+            .collect();
+        move |var| map.get(var).cloned()
+    }
+
+    fn layer(source: Source, text: &str) -> Layer {
+        Layer {
+            source,
+            settings: toml::from_str(text).unwrap(),
+        }
+    }
+
+    #[test]
+    fn prefers_primary_interval_env() {
+        let settings = Settings::resolve(
+            &[],
+            env_from(&[(WAIT_INTERVAL_ENV, "45"), (LEGACY_WAIT_INTERVAL_ENV, "90")]),
+        );
+        assert_eq!(settings.wait_interval.value, Duration::from_secs(45));
+        assert_eq!(
+            settings.wait_interval.source,
+            Source::Env(WAIT_INTERVAL_ENV)
+        );
+    }
+
+    #[test]
+    fn accepts_human_interval_env() {
+        let settings = Settings::resolve(&[], env_from(&[(WAIT_INTERVAL_ENV, "250ms")]));
+        assert_eq!(settings.wait_interval.value, Duration::from_millis(250));
+    }
+
+    #[test]
+    fn falls_back_to_legacy_env() {
+        let settings = Settings::resolve(&[], env_from(&[(LEGACY_WAIT_INTERVAL_ENV, "90")]));
+        assert_eq!(settings.wait_interval.value, Duration::from_secs(90));
+    }
+
+    #[test]
+    fn returns_default_on_invalid_values() {
+        let settings = Settings::resolve(&[], env_from(&[(WAIT_INTERVAL_ENV, "not-a-number")]));
+        assert_eq!(settings.wait_interval.value, WAIT_INTERVAL_DEFAULT);
+        assert_eq!(settings.wait_interval.source, Source::Default);
+    }
+
+    #[test]
+    fn project_layer_overrides_user_layer_and_env_overrides_both() {
+        let user = PathBuf::from("/home/u/.config/codex-warden/config.toml");
+        let project = PathBuf::from("/work/.codex-warden.toml");
+        let layers = [
+            layer(
+                Source::UserFile(user.clone()),
+                "max_record_age = \"6h\"\nwait_interval = 10\n[backends.claude]\nbinary = \"claude\"\nargs = [\"-p\"]",
+            ),
+            layer(
+                Source::ProjectFile(project.clone()),
+                "wait_interval = \"5s\"\n[backends.claude]\nbinary = \"/opt/claude\"",
+            ),
+        ];
+        let settings = Settings::resolve(&layers, env_from(&[(CODEX_BIN_ENV, "/opt/codex")]));
+
+        assert_eq!(settings.max_record_age.value, Duration::from_secs(6 * 3600));
+        assert_eq!(settings.max_record_age.source, Source::UserFile(user));
+        assert_eq!(settings.wait_interval.value, Duration::from_secs(5));
+        assert_eq!(
+            settings.wait_interval.source,
+            Source::ProjectFile(project.clone())
+        );
+        let claude = &settings.backends["claude"];
+        assert_eq!(claude.value.binary.as_deref(), Some("/opt/claude"));
+        assert_eq!(claude.value.args, Some(vec!["-p".to_owned()]));
+        assert_eq!(claude.source, Source::ProjectFile(project));
+        assert_eq!(settings.codex_bin.value.as_deref(), Some("/opt/codex"));
+        assert_eq!(settings.codex_bin.source, Source::Env(CODEX_BIN_ENV));
+    }
+}
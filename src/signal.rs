@@ -1,18 +1,66 @@
-﻿use crate::platform;
+﻿use crate::escalation::{EscalationPolicy, StopSignal};
+use crate::platform;
 use std::io;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU32, Ordering};
 
 static CHILD_PID: AtomicU32 = AtomicU32::new(0);
+/// 收到过终止信号；重试之间没有子进程可转发，监督进程据此放弃后续尝试
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+/// 后端停止策略的第一个信号，由信号处理器转发给子进程
+static FIRST_SIGNAL: AtomicU8 = AtomicU8::new(NO_SIGNAL);
+/// 最终让子进程退出的信号；处理器转发后先写入第一个信号，看门狗升级后再覆盖
+static STOPPED_BY: AtomicU8 = AtomicU8::new(NO_SIGNAL);
+
+/// 监督进程已写完任务记录；Windows 关闭控制台时处理器等到这一刻再返回
+static FINISHED: AtomicBool = AtomicBool::new(false);
+
+const NO_SIGNAL: u8 = 0;
 
 pub struct SignalGuard;
 
+impl SignalGuard {
+    /// 因终止信号而停止子进程时最终生效的信号
+    pub fn stopped_by(&self) -> Option<StopSignal> {
+        decode(STOPPED_BY.load(Ordering::SeqCst))
+    }
+}
+
 impl Drop for SignalGuard {
     fn drop(&mut self) {
         CHILD_PID.store(0, Ordering::SeqCst);
     }
 }
 
-pub fn install(child_pid: u32) -> io::Result<SignalGuard> {
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// 处理器已把第一个信号转发给了子进程，看门狗只需继续升级
+pub fn forwarded() -> bool {
+    STOPPED_BY.load(Ordering::SeqCst) != NO_SIGNAL
+}
+
+/// 任务记录与历史都已写完，之后进程可以随时结束
+pub fn finished() {
+    FINISHED.store(true, Ordering::SeqCst);
+}
+
+/// 看门狗按策略升级后记下最终生效的信号
+pub fn record_stopped_by(signal: Option<StopSignal>) {
+    if let Some(signal) = signal {
+        STOPPED_BY.store(encode(signal), Ordering::SeqCst);
+    }
+}
+
+pub fn install(child_pid: u32, policy: &EscalationPolicy) -> io::Result<SignalGuard> {
+    STOPPED_BY.store(NO_SIGNAL, Ordering::SeqCst);
+    FIRST_SIGNAL.store(
+        policy
+            .steps
+            .first()
+            .map_or(NO_SIGNAL, |step| encode(step.signal)),
+        Ordering::SeqCst,
+    );
     CHILD_PID.store(child_pid, Ordering::SeqCst);
 
     // 使用更安全的信号处理方法
@@ -64,8 +112,8 @@ unsafe fn setup_signal_handlers_safe() {
         sigterm_action.sa_flags = libc::SA_RESTART;
 
         // 设置信号处理器
-        sigint_action.sa_sigaction = handler as usize;
-        sigterm_action.sa_sigaction = handler as usize;
+        sigint_action.sa_sigaction = handler as *const () as usize;
+        sigterm_action.sa_sigaction = handler as *const () as usize;
 
         // 清空信号掩码
         let mut empty_set: libc::sigset_t = std::mem::zeroed();
@@ -79,32 +127,81 @@ unsafe fn setup_signal_handlers_safe() {
     }
 }
 
+/// 处理器中只允许异步信号安全的操作：置位标志，并把策略的第一个信号转发一次；
+/// 后续的宽限等待与升级由看门狗线程完成
 #[cfg(unix)]
 fn handle_unix_signal(signum: libc::c_int) {
     match signum {
         libc::SIGINT | libc::SIGTERM => {
+            INTERRUPTED.store(true, Ordering::SeqCst);
             let pid = CHILD_PID.load(Ordering::SeqCst);
-            if pid != 0 {
-                platform::terminate_process(pid);
+            let first = FIRST_SIGNAL.load(Ordering::SeqCst);
+            if pid != 0
+                && let Some(signal) = decode(first)
+                && STOPPED_BY
+                    .compare_exchange(NO_SIGNAL, first, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+            {
+                platform::forward_signal(pid, signal);
             }
         }
         _ => {}
     }
 }
 
+fn encode(signal: StopSignal) -> u8 {
+    match signal {
+        StopSignal::Int => 1,
+        StopSignal::Term => 2,
+        StopSignal::Kill => 3,
+    }
+}
+
+fn decode(code: u8) -> Option<StopSignal> {
+    match code {
+        1 => Some(StopSignal::Int),
+        2 => Some(StopSignal::Term),
+        3 => Some(StopSignal::Kill),
+        _ => None,
+    }
+}
+
 #[cfg(windows)]
 fn setup_windows_signal_handler() -> io::Result<()> {
+    use std::thread;
+    use std::time::{Duration, Instant};
     use windows::Win32::Foundation::BOOL;
     use windows::Win32::System::Console::{
         CTRL_BREAK_EVENT, CTRL_C_EVENT, CTRL_CLOSE_EVENT, SetConsoleCtrlHandler,
     };
 
+    /// 系统在关闭控制台约 5 秒后强制结束进程，等待监督进程收尾不能超过这个期限
+    const CLOSE_GRACE: Duration = Duration::from_secs(4);
+
+    // 控制台处理器运行在独立线程上而非信号上下文，可以直接结束子进程树；
+    // 关闭控制台时处理器一返回进程就会被结束，看门狗来不及执行
     unsafe extern "system" fn handler(ctrl_type: u32) -> BOOL {
         match ctrl_type {
             CTRL_C_EVENT | CTRL_BREAK_EVENT | CTRL_CLOSE_EVENT => {
+                INTERRUPTED.store(true, Ordering::SeqCst);
                 let pid = CHILD_PID.load(Ordering::SeqCst);
-                if pid != 0 {
-                    platform::terminate_process(pid);
+                if pid != 0
+                    && STOPPED_BY
+                        .compare_exchange(
+                            NO_SIGNAL,
+                            encode(StopSignal::Kill),
+                            Ordering::SeqCst,
+                            Ordering::SeqCst,
+                        )
+                        .is_ok()
+                {
+                    platform::stop_process(pid, &EscalationPolicy::default());
+                }
+                if ctrl_type == CTRL_CLOSE_EVENT {
+                    let deadline = Instant::now() + CLOSE_GRACE;
+                    while !FINISHED.load(Ordering::SeqCst) && Instant::now() < deadline {
+                        thread::sleep(Duration::from_millis(50));
+                    }
                 }
                 BOOL(1)
             }
@@ -0,0 +1,153 @@
+use crate::cli::{self, UsageError};
+use crate::platform;
+use crate::registry::{self, RegistryEntry, RegistryError, TaskRegistry};
+use crate::task_record::{TaskRecord, TaskStatus};
+use chrono::{DateTime, Local, Utc};
+use serde::Serialize;
+use std::ffi::OsString;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StatusError {
+    #[error("registry error: {0}")]
+    Registry(#[from] RegistryError),
+    #[error("{0}")]
+    Usage(#[from] UsageError),
+    #[error("serialization failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+#[derive(Debug, Default)]
+struct StatusOptions {
+    json: bool,
+}
+
+impl StatusOptions {
+    fn parse(args: &[String]) -> Result<Self, UsageError> {
+        let mut options = StatusOptions::default();
+        for arg in args {
+            match arg.as_str() {
+                "--json" => options.json = true,
+                other => return Err(cli::unknown_argument(other)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+#[derive(Serialize)]
+struct StatusRow<'a> {
+    age_secs: i64,
+    /// 仅运行中的任务有值：距最近一次输出的秒数
+    silent_secs: Option<i64>,
+    /// 仅排队中的任务有值，从 1 开始
+    queue_position: Option<usize>,
+    #[serde(flatten)]
+    record: &'a TaskRecord,
+}
+
+/// 只读地列出注册表中的全部任务；与 `wait` 不同，这里不会清理或删除任何记录
+pub fn run(args: &[OsString]) -> Result<(), StatusError> {
+    let options = StatusOptions::parse(&cli::to_strings(args)?)?;
+    let registry = TaskRegistry::connect()?;
+    let mut entries = registry.entries()?;
+    entries.sort_by_key(|entry| entry.record.started_at);
+
+    let now = Utc::now();
+    if options.json {
+        let rows: Vec<StatusRow<'_>> = entries
+            .iter()
+            .map(|entry| StatusRow {
+                age_secs: age_of(&entry.record, now).num_seconds(),
+                silent_secs: entry
+                    .record
+                    .silent_for(now)
+                    .map(|silent| silent.num_seconds()),
+                queue_position: registry::queue_position(&entries, &entry.task_id),
+                record: &entry.record,
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+    } else {
+        let limit = registry.max_concurrent();
+        if limit > 0 {
+            println!(
+                "Concurrency slots in use: {}/{limit}",
+                registry.slots_in_use(platform::slot_owner_alive)
+            );
+        }
+        print!("{}", render_table(&entries, now));
+    }
+    Ok(())
+}
+
+fn age_of(record: &TaskRecord, now: DateTime<Utc>) -> chrono::Duration {
+    record
+        .completed_at
+        .unwrap_or(now)
+        .signed_duration_since(record.started_at)
+}
+
+fn render_table(entries: &[RegistryEntry], now: DateTime<Utc>) -> String {
+    if entries.is_empty() {
+        return "No tasks registered.\n".to_owned();
+    }
+
+    let header = [
+        "TASK", "PID", "BACKEND", "STATUS", "STARTED", "AGE", "SILENT", "MANAGER", "EXIT", "LOG",
+    ];
+    let rows: Vec<Vec<String>> = entries
+        .iter()
+        .map(|entry| {
+            let record = &entry.record;
+            vec![
+                entry.task_id.clone(),
+                entry.pid.to_string(),
+                record.backend.clone(),
+                status_label(entries, entry),
+                record
+                    .started_at
+                    .with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string(),
+                format_age(age_of(record, now)),
+                record
+                    .silent_for(now)
+                    .map_or_else(|| "-".to_owned(), format_age),
+                record
+                    .manager_pid
+                    .map_or_else(|| "-".to_owned(), |pid| pid.to_string()),
+                record
+                    .exit_code
+                    .map_or_else(|| "-".to_owned(), |code| code.to_string()),
+                record.log_path.clone(),
+            ]
+        })
+        .collect();
+    cli::render_table(&header, &rows)
+}
+
+fn status_label(entries: &[RegistryEntry], entry: &RegistryEntry) -> String {
+    match entry.record.status {
+        TaskStatus::Queued => match registry::queue_position(entries, &entry.task_id) {
+            Some(position) => format!("queued #{position}"),
+            None => "queued".to_owned(),
+        },
+        TaskStatus::Running => "running".to_owned(),
+        TaskStatus::CompletedButUnread => "completed".to_owned(),
+    }
+}
+
+pub fn format_age(age: chrono::Duration) -> String {
+    let seconds = age.num_seconds().max(0);
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    let remaining = seconds % 60;
+    if hours > 0 {
+        format!("{hours}h{minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m{remaining:02}s")
+    } else {
+        format!("{remaining}s")
+    }
+}
@@ -1,17 +1,31 @@
-use crate::config::CODEX_BIN;
-use crate::logging::debug;
+use crate::backend::Backend;
+use crate::config::{
+    DETACHED_CHILD_ENV, HEARTBEAT_INTERVAL, IDLE_TIMEOUT_RESULT, RETRY_POLL_INTERVAL,
+    SIGNAL_POLL_INTERVAL, SUMMARY_LINES, TIMED_OUT_RESULT, TIMEOUT_EXIT_CODE,
+};
+use crate::delegate::Delegate;
+use crate::duration::format_duration;
+use crate::escalation::{self, EscalationPolicy, StopSignal};
+use crate::history;
+use crate::launch::{LaunchOptions, OutputMode};
+use crate::logging::{debug, warn};
+use crate::logs;
 use crate::platform::{self, ChildResources};
-use crate::registry::{RegistryError, TaskRegistry};
+use crate::registry::{self, CleanupReason, RegistryError, TaskRegistry};
+use crate::settings;
 use crate::signal;
-use crate::task_record::TaskRecord;
+use crate::task_record::{Attempt, TaskRecord, TaskStatus};
 use chrono::{DateTime, Utc};
 use std::ffi::OsString;
-use std::fs::OpenOptions;
-use std::io::{self, BufWriter, Read, Write};
-use std::path::PathBuf;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -20,15 +34,77 @@ pub enum ProcessError {
     Io(#[from] io::Error),
     #[error("Registry error: {0}")]
     Registry(#[from] RegistryError),
+    #[error("detached supervisor failed: {0}")]
+    Detach(String),
 }
 
-pub fn execute_codex(registry: &TaskRegistry, args: &[OsString]) -> Result<i32, ProcessError> {
+static ANNOUNCED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_detached_child() -> bool {
+    std::env::var_os(DETACHED_CHILD_ENV).is_some()
+}
+
+/// 以后台监督进程重新执行自身，等待其完成任务登记后返回任务信息
+pub fn spawn_detached(args: &[OsString]) -> Result<i32, ProcessError> {
+    let exe = std::env::current_exe()?;
+    let mut command = Command::new(exe);
+    command.args(args);
+    command.env(DETACHED_CHILD_ENV, "1");
+    command.stdin(Stdio::null());
+    command.stdout(Stdio::piped());
+    command.stderr(Stdio::null());
+    platform::prepare_detached(&mut command)?;
+
+    let mut child = command.spawn()?;
+    let mut line = String::new();
+    if let Some(stdout) = child.stdout.take() {
+        BufReader::new(stdout).read_line(&mut line)?;
+    }
+
+    let handshake: serde_json::Value = match serde_json::from_str(line.trim()) {
+        Ok(value) => value,
+        Err(_) => {
+            let status = child.wait()?;
+            return Err(ProcessError::Detach(format!(
+                "exited before registration ({status})"
+            )));
+        }
+    };
+    if let Some(message) = handshake.get("error").and_then(|value| value.as_str()) {
+        let _ = child.wait();
+        return Err(ProcessError::Detach(message.to_owned()));
+    }
+
+    println!("{}", line.trim());
+    Ok(0)
+}
+
+/// 后台监督进程通过标准输出向启动者汇报失败原因；握手完成后启动者已退出，不再汇报
+pub fn report_detached_failure(message: &str) {
+    if !ANNOUNCED.load(Ordering::SeqCst) {
+        let _ = writeln!(io::stdout(), "{}", serde_json::json!({ "error": message }));
+    }
+}
+
+pub fn execute_codex(
+    registry: &TaskRegistry,
+    backend: &Backend,
+    launch: &LaunchOptions,
+    args: &[OsString],
+) -> Result<i32, ProcessError> {
     platform::init_platform();
+    let detached = is_detached_child();
+    // 后台模式下标准输出仅用于握手，Codex 输出只写入日志
+    let output = if detached {
+        OutputMode::Quiet
+    } else {
+        OutputMode::resolve(launch.output)
+    };
 
     registry.sweep_stale_entries(
         Utc::now(),
-        platform::process_alive,
-        &platform::terminate_process,
+        platform::same_process_alive,
+        &escalation::stop_task,
     )?;
 
     let should_register = args
@@ -36,116 +112,619 @@ pub fn execute_codex(registry: &TaskRegistry, args: &[OsString]) -> Result<i32,
         .and_then(|arg| arg.to_str())
         .is_some_and(|s| !s.eq_ignore_ascii_case("wait"));
 
-    let mut command = Command::new(CODEX_BIN);
-    command.args(args);
-    command.stdin(Stdio::inherit());
-    command.stdout(Stdio::piped());
-    command.stderr(Stdio::piped());
-    platform::prepare_command(&mut command)?;
-
-    let mut child = command.spawn()?;
-    let child_pid = child.id();
+    let delegate = Delegate::resolve(backend)?;
+    let session = Session {
+        registry,
+        backend,
+        launch,
+        args,
+        delegate_version: delegate.version(),
+        delegate,
+        task_id: registry::new_task_id(),
+        output,
+        detached,
+        should_register,
+    };
 
-    let log_path = match generate_log_path(child_pid) {
-        Ok(path) => path,
-        Err(err) => {
-            platform::terminate_process(child_pid);
-            let _ = child.wait();
-            return Err(err.into());
+    let mut registration_guard = None;
+    // 槽位在全部尝试（包括重试前的等待）期间一直占用
+    let _slot = match session.admit(&mut registration_guard)? {
+        Admission::Unlimited => None,
+        Admission::Slot(slot) => Some(slot),
+        Admission::Cancelled => {
+            signal::finished();
+            return Ok(1);
         }
     };
-
-    let log_file = match OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .open(&log_path)
-    {
-        Ok(file) => file,
-        Err(err) => {
-            platform::terminate_process(child_pid);
-            let _ = child.wait();
-            return Err(err.into());
+    let mut number = 1;
+    let (outcome, attempt) = loop {
+        let outcome = session.run_attempt(number, &mut registration_guard)?;
+        let attempt = outcome.to_attempt(number, backend);
+        if launch.retry.retries > 0
+            && let Some(guard) = &registration_guard
+        {
+            guard.record_attempt(attempt.clone());
+        }
+        if !session.should_retry(&outcome, &attempt, registration_guard.as_ref()) {
+            break (outcome, attempt);
         }
+        let delay = launch.retry.delay(number);
+        warn(format!(
+            "attempt {number} of task {} failed ({}), retrying in {}",
+            session.task_id,
+            attempt.result.as_deref().unwrap_or("unknown"),
+            format_duration(delay)
+        ));
+        if !session.back_off(delay, registration_guard.as_ref()) {
+            break (outcome, attempt);
+        }
+        number += 1;
     };
 
-    debug(format!(
-        "Started Codex process pid={} log={}",
-        child_pid,
-        log_path.display()
-    ));
+    if output == OutputMode::Summary {
+        print_summary(&outcome.log_path)?;
+    }
 
-    let _resources: ChildResources = platform::after_spawn(&child)?;
-    let signal_guard = signal::install(child_pid)?;
+    if let Some(guard) = registration_guard {
+        // 注册表中的记录已被清理时不再补写，扫描方已经写过历史
+        if let Ok(record) = guard.mark_completed(
+            attempt.result,
+            attempt.exit_code,
+            attempt.completed_at,
+            outcome.stopped_by,
+        ) {
+            history::append(&record);
+        }
+    }
+    signal::finished();
 
-    let log_writer = Arc::new(Mutex::new(BufWriter::new(log_file)));
-    let mut copy_handles = Vec::new();
+    if outcome.expired.is_some() {
+        return Ok(TIMEOUT_EXIT_CODE);
+    }
+    Ok(extract_exit_code(outcome.status))
+}
 
-    if let Some(stdout) = child.stdout.take() {
-        copy_handles.push(spawn_copy(stdout, log_writer.clone(), StreamMirror::Stdout));
+/// 一个任务的各次尝试共用的上下文
+struct Session<'a> {
+    registry: &'a TaskRegistry,
+    backend: &'a Backend,
+    launch: &'a LaunchOptions,
+    args: &'a [OsString],
+    delegate: Delegate,
+    delegate_version: Option<String>,
+    task_id: String,
+    output: OutputMode,
+    detached: bool,
+    should_register: bool,
+}
+
+/// 一次尝试结束时的状态
+struct AttemptOutcome {
+    pid: u32,
+    started_at: DateTime<Utc>,
+    completed_at: DateTime<Utc>,
+    status: ExitStatus,
+    expired: Option<(Expiry, StopSignal)>,
+    stopped_by: Option<StopSignal>,
+    log_path: PathBuf,
+}
+
+impl AttemptOutcome {
+    fn to_attempt(&self, number: u32, backend: &Backend) -> Attempt {
+        let success = self.status.success() && self.expired.is_none();
+        let exit_code = match self.expired {
+            Some(_) => Some(TIMEOUT_EXIT_CODE),
+            None => self.status.code(),
+        };
+        let extracted = match (&backend.result_extractor, success) {
+            (Some(extractor), true) => extractor.extract(&self.log_path).unwrap_or_else(|err| {
+                debug(format!("result extraction failed: {err}"));
+                None
+            }),
+            _ => None,
+        };
+        let result = match (success, exit_code) {
+            _ if let Some((expiry, _)) = self.expired => Some(expiry.result().to_owned()),
+            (true, _) => extracted.or_else(|| Some("success".to_owned())),
+            (false, Some(code)) => Some(format!("failed_with_exit_code_{code}")),
+            (false, None) => Some("failed_without_exit_code".to_owned()),
+        };
+        Attempt {
+            number,
+            pid: self.pid,
+            started_at: self.started_at,
+            completed_at: self.completed_at,
+            exit_code,
+            result,
+            stopped_by: self.stopped_by.map(|signal| signal.as_str().to_owned()),
+            log_path: self.log_path.to_string_lossy().into_owned(),
+        }
     }
-    if let Some(stderr) = child.stderr.take() {
-        copy_handles.push(spawn_copy(stderr, log_writer.clone(), StreamMirror::Stderr));
+}
+
+/// 全局并发上限下的准入结果
+enum Admission<'a> {
+    /// 未设置上限，或是不登记的命令
+    Unlimited,
+    Slot(SlotGuard<'a>),
+    /// 排队期间被 `kill` 取消、被清理或收到终止信号
+    Cancelled,
+}
+
+impl Session<'_> {
+    /// 通知段中的上限非 0 时先取得并发槽位；已有任务在排队或槽位已满时登记为排队状态，
+    /// 按入队顺序等待
+    fn admit<'s>(
+        &'s self,
+        registration: &mut Option<RegistrationGuard<'s>>,
+    ) -> Result<Admission<'s>, ProcessError> {
+        // 只有显式配置时才写入（0 即解除）；未配置的启动不能替其他项目放开排队中的任务
+        if let Some(limit) = settings::get().max_concurrent.value {
+            self.registry.set_max_concurrent(limit);
+        }
+        if !self.should_register || self.registry.max_concurrent() == 0 {
+            return Ok(Admission::Unlimited);
+        }
+
+        let pid = platform::current_pid();
+        let start_time = platform::process_start_time(pid);
+        let acquire = || {
+            self.registry
+                .try_acquire_slot(pid, start_time, platform::slot_owner_alive)
+                .map(|index| {
+                    Admission::Slot(SlotGuard {
+                        registry: self.registry,
+                        index,
+                        pid,
+                        start_time,
+                    })
+                })
+        };
+        let queue_empty = self
+            .registry
+            .entries()?
+            .iter()
+            .all(|entry| entry.record.status != TaskStatus::Queued);
+        if queue_empty && let Some(admission) = acquire() {
+            return Ok(admission);
+        }
+
+        let enqueued_at = Utc::now();
+        let log_path = generate_log_path(&self.task_id, 1)
+            .to_string_lossy()
+            .into_owned();
+        let record = self
+            .new_record(0, None, enqueued_at, log_path.clone())
+            .queued();
+        self.registry.register(&record)?;
+        *registration = Some(RegistrationGuard::new(self.registry, self.task_id.clone()));
+        self.announce(None, &log_path)?;
+        debug(format!(
+            "task {} queued for a concurrency slot",
+            self.task_id
+        ));
+        // 排队期间没有子进程，处理器只记下中断
+        let _signal_guard = signal::install(0, &self.backend.stop_policy)?;
+
+        let mut last_beat = Instant::now();
+        loop {
+            let seen = self.registry.generation();
+            if signal::interrupted() {
+                if let Ok(record) = self
+                    .registry
+                    .mark_cancelled(&self.task_id, CleanupReason::CancelledByUser)
+                {
+                    history::append(&record);
+                }
+                if let Some(guard) = registration.take() {
+                    guard.keep();
+                }
+                return Ok(Admission::Cancelled);
+            }
+            let limit = self.registry.max_concurrent();
+            if limit == 0 {
+                return Ok(Admission::Unlimited);
+            }
+            let entries = self.registry.entries()?;
+            let Some(position) = registry::queue_position(&entries, &self.task_id) else {
+                // 取消或清理的一方已写入历史，记录保留给 `wait` 读取
+                if let Some(guard) = registration.take() {
+                    guard.keep();
+                }
+                return Ok(Admission::Cancelled);
+            };
+            let free = (limit as usize)
+                .saturating_sub(self.registry.slots_in_use(platform::slot_owner_alive));
+            if position <= free
+                && let Some(admission) = acquire()
+            {
+                return Ok(admission);
+            }
+            if last_beat.elapsed() >= HEARTBEAT_INTERVAL {
+                let _ = self.registry.heartbeat(&self.task_id, Utc::now(), None);
+                last_beat = Instant::now();
+            }
+            self.registry.wait_for_change(seen, RETRY_POLL_INTERVAL);
+        }
     }
 
-    let registration_guard = if should_register {
-        let record = TaskRecord::new(
-            Utc::now(),
-            child_pid.to_string(),
-            log_path.to_string_lossy().into_owned(),
+    fn new_record(
+        &self,
+        pid: u32,
+        child_start_time: Option<u64>,
+        started_at: DateTime<Utc>,
+        log_path: String,
+    ) -> TaskRecord {
+        TaskRecord::new(
+            pid,
+            started_at,
+            self.task_id.clone(),
+            log_path,
             Some(platform::current_pid()),
-        );
-        if let Err(err) = registry.register(child_pid, &record) {
-            platform::terminate_process(child_pid);
-            let _ = child.wait();
-            return Err(err.into());
+        )
+        .with_start_times(
+            child_start_time,
+            platform::process_start_time(platform::current_pid()),
+        )
+        .with_backend(&self.backend.name)
+        .with_labels(&self.launch.tags, self.launch.group.as_deref())
+        .with_cwd(std::env::current_dir().ok())
+        .with_heartbeat(started_at)
+        .with_binary(
+            self.delegate.path.to_string_lossy().into_owned(),
+            self.delegate_version.clone(),
+        )
+    }
+
+    /// 后台模式下向启动者输出握手信息，只输出一次；排队中的任务尚无 PID
+    fn announce(&self, pid: Option<u32>, log_path: &str) -> io::Result<()> {
+        if !self.detached || ANNOUNCED.load(Ordering::SeqCst) {
+            return Ok(());
         }
-        Some(RegistrationGuard::new(registry, child_pid))
-    } else {
-        None
-    };
+        let status = if pid.is_some() { "running" } else { "queued" };
+        let mut stdout = io::stdout().lock();
+        writeln!(
+            stdout,
+            "{}",
+            serde_json::json!({
+                "task_id": self.task_id,
+                "pid": pid,
+                "status": status,
+                "log_path": log_path,
+            })
+        )?;
+        stdout.flush()?;
+        ANNOUNCED.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// 启动并监督一次尝试；第一次尝试登记任务并完成后台握手，之后的尝试只更新记录
+    fn run_attempt<'s>(
+        &'s self,
+        number: u32,
+        registration: &mut Option<RegistrationGuard<'s>>,
+    ) -> Result<AttemptOutcome, ProcessError> {
+        let log_path = generate_log_path(&self.task_id, number);
+        let log_file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&log_path)?;
+
+        let mut command = self.delegate.command();
+        command.args(self.args);
+        command.env_remove(DETACHED_CHILD_ENV);
+        command.stdin(Stdio::inherit());
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::piped());
+        platform::prepare_command(&mut command)?;
 
-    let status = child.wait()?;
-    drop(signal_guard);
+        let mut child = command.spawn()?;
+        let child_pid = child.id();
+        // 尽早读取，子进程很快退出时仍能拿到启动时间
+        let child_start_time = platform::process_start_time(child_pid);
+        let started_at = Utc::now();
 
-    for handle in copy_handles {
-        match handle.join() {
-            Ok(result) => result?,
-            Err(_) => {
-                return Err(io::Error::other("Log writer thread failed").into());
+        debug(format!(
+            "Started Codex process pid={} task={} attempt={} log={}",
+            child_pid,
+            self.task_id,
+            number,
+            log_path.display()
+        ));
+
+        let _resources: ChildResources = platform::after_spawn(&child)?;
+        let signal_guard = signal::install(child_pid, &self.backend.stop_policy)?;
+
+        let log_writer = Arc::new(Mutex::new(BufWriter::new(log_file)));
+        let activity = Arc::new(OutputActivity::new());
+        let mut copy_handles = Vec::new();
+
+        let (stdout_mirror, stderr_mirror) = match self.output {
+            OutputMode::Mirror => (StreamMirror::Stdout, StreamMirror::Stderr),
+            OutputMode::Quiet | OutputMode::Summary => (StreamMirror::None, StreamMirror::None),
+        };
+        if let Some(stdout) = child.stdout.take() {
+            copy_handles.push(spawn_copy(
+                stdout,
+                log_writer.clone(),
+                stdout_mirror,
+                activity.clone(),
+            ));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            copy_handles.push(spawn_copy(
+                stderr,
+                log_writer.clone(),
+                stderr_mirror,
+                activity.clone(),
+            ));
+        }
+
+        let log_path_text = log_path.to_string_lossy().into_owned();
+        if registration.is_some() {
+            // 等待期间记录可能已被清理，此时只是写入失败，后续由 `should_retry` 停止重试
+            let _ = self.registry.update(&self.task_id, |record| {
+                record.dequeued(started_at).with_child(
+                    child_pid,
+                    child_start_time,
+                    log_path_text.clone(),
+                )
+            });
+        } else if self.should_register {
+            let record = self.new_record(
+                child_pid,
+                child_start_time,
+                started_at,
+                log_path_text.clone(),
+            );
+            if let Err(err) = self.registry.register(&record) {
+                platform::terminate_process(child_pid);
+                let _ = child.wait();
+                return Err(err.into());
             }
+            *registration = Some(RegistrationGuard::new(self.registry, self.task_id.clone()));
+        }
+
+        self.announce(Some(child_pid), &log_path_text)?;
+
+        let watchdog = Watchdog {
+            pid: child_pid,
+            timeout: self.launch.timeout,
+            idle_timeout: self.launch.idle_timeout,
+            policy: &self.backend.stop_policy,
+            activity: &activity,
+            registration: registration
+                .as_ref()
+                .map(|_| (self.registry, self.task_id.as_str())),
+        };
+        let (status, expired) = thread::scope(|scope| {
+            let (done, finished) = mpsc::channel();
+            let handle = scope.spawn(|| watchdog.run(finished));
+            let status = child.wait();
+            let _ = done.send(());
+            (status, handle.join().ok().flatten())
+        });
+        let status = status?;
+        let stopped_by = expired
+            .map(|(_, signal)| signal)
+            .or_else(|| signal_guard.stopped_by());
+        drop(signal_guard);
+
+        for handle in copy_handles {
+            match handle.join() {
+                Ok(result) => result?,
+                Err(_) => {
+                    return Err(io::Error::other("Log writer thread failed").into());
+                }
+            }
+        }
+
+        {
+            let mut writer = log_writer
+                .lock()
+                .map_err(|_| io::Error::other("Log writer lock poisoned"))?;
+            writer.flush()?;
+            writer.get_ref().sync_all()?;
+        }
+
+        Ok(AttemptOutcome {
+            pid: child_pid,
+            started_at,
+            completed_at: Utc::now(),
+            status,
+            expired,
+            stopped_by,
+            log_path,
+        })
+    }
+
+    /// 还有剩余次数、失败满足 `--retry-on`，且任务没有被用户中断或取消
+    fn should_retry(
+        &self,
+        outcome: &AttemptOutcome,
+        attempt: &Attempt,
+        registration: Option<&RegistrationGuard<'_>>,
+    ) -> bool {
+        let retry = &self.launch.retry;
+        let failed = !outcome.status.success() || outcome.expired.is_some();
+        let interrupted =
+            signal::interrupted() || (outcome.stopped_by.is_some() && outcome.expired.is_none());
+        failed
+            && attempt.number <= retry.retries
+            && !interrupted
+            && registration.is_none_or(RegistrationGuard::still_running)
+            && retry.matches(attempt.exit_code, &outcome.log_path)
+    }
+
+    /// 重试前等待；期间继续写入心跳，被中断或取消时返回 `false`
+    fn back_off(&self, delay: Duration, registration: Option<&RegistrationGuard<'_>>) -> bool {
+        let deadline = Instant::now() + delay;
+        let mut last_beat = Instant::now();
+        loop {
+            if signal::interrupted() || registration.is_some_and(|guard| !guard.still_running()) {
+                return false;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return true;
+            }
+            if last_beat.elapsed() >= HEARTBEAT_INTERVAL {
+                let _ = self.registry.heartbeat(&self.task_id, Utc::now(), None);
+                last_beat = Instant::now();
+            }
+            let seen = self.registry.generation();
+            self.registry
+                .wait_for_change(seen, remaining.min(RETRY_POLL_INTERVAL));
         }
     }
+}
 
-    {
-        let mut writer = log_writer
+/// 看门狗终止子进程的原因
+#[derive(Debug, Clone, Copy)]
+enum Expiry {
+    Timeout,
+    Idle,
+}
+
+impl Expiry {
+    fn result(self) -> &'static str {
+        match self {
+            Expiry::Timeout => TIMED_OUT_RESULT,
+            Expiry::Idle => IDLE_TIMEOUT_RESULT,
+        }
+    }
+}
+
+/// 子进程最近一次产生输出的时刻，由输出复制线程更新
+struct OutputActivity {
+    last: Mutex<(Instant, Option<DateTime<Utc>>)>,
+}
+
+impl OutputActivity {
+    fn new() -> Self {
+        Self {
+            last: Mutex::new((Instant::now(), None)),
+        }
+    }
+
+    fn touch(&self) {
+        if let Ok(mut last) = self.last.lock() {
+            *last = (Instant::now(), Some(Utc::now()));
+        }
+    }
+
+    /// 距最近一次输出（尚无输出时为启动）的时长，以及该次输出的时间
+    fn snapshot(&self) -> (Duration, Option<DateTime<Utc>>) {
+        self.last
             .lock()
-            .map_err(|_| io::Error::other("Log writer lock poisoned"))?;
-        writer.flush()?;
-        writer.get_ref().sync_all()?;
+            .map_or((Duration::ZERO, None), |last| (last.0.elapsed(), last.1))
     }
+}
 
-    if let Some(guard) = registration_guard {
-        let completed_at = Utc::now();
-        let exit_code = status.code();
-        let result = match (status.success(), exit_code) {
-            (true, _) => Some("success".to_owned()),
-            (false, Some(code)) => Some(format!("failed_with_exit_code_{code}")),
-            (false, None) => Some("failed_without_exit_code".to_owned()),
-        };
-        let _ = guard.mark_completed(result, exit_code, completed_at);
+/// 与 `child.wait()` 并行运行：执行 `--timeout` / `--idle-timeout`，并定期写入心跳与最近输出时间
+struct Watchdog<'a> {
+    pid: u32,
+    timeout: Option<Duration>,
+    idle_timeout: Option<Duration>,
+    policy: &'a EscalationPolicy,
+    activity: &'a OutputActivity,
+    /// 任务已登记时的注册表与任务 id
+    registration: Option<(&'a TaskRegistry, &'a str)>,
+}
+
+impl Watchdog<'_> {
+    /// 子进程退出时 `finished` 收到通知；返回 `Some` 表示子进程是因超时被看门狗终止的。
+    /// 收到终止信号时在这里按策略升级，结果记入 `signal` 模块
+    fn run(&self, finished: mpsc::Receiver<()>) -> Option<(Expiry, StopSignal)> {
+        let started = Instant::now();
+        let mut last_beat: Option<Instant> = None;
+        loop {
+            if signal::interrupted() {
+                let stopped_by = if signal::forwarded() {
+                    platform::continue_stop(self.pid, self.policy)
+                } else {
+                    platform::stop_process(self.pid, self.policy)
+                };
+                signal::record_stopped_by(stopped_by);
+                self.beat(self.activity.snapshot().1);
+                return None;
+            }
+            let elapsed = started.elapsed();
+            let (silent, last_output_at) = self.activity.snapshot();
+            if let Some((expiry, limit)) = self.expiry(elapsed, silent) {
+                debug(format!(
+                    "pid={} exceeded {} {}, stopping",
+                    self.pid,
+                    expiry.result(),
+                    format_duration(limit)
+                ));
+                return platform::stop_process(self.pid, self.policy)
+                    .map(|signal| (expiry, signal));
+            }
+            if last_beat.is_none_or(|at| at.elapsed() >= HEARTBEAT_INTERVAL) {
+                self.beat(last_output_at);
+                last_beat = Some(Instant::now());
+            }
+
+            let mut pause = SIGNAL_POLL_INTERVAL;
+            if let Some(limit) = self.timeout {
+                pause = pause.min(limit - elapsed);
+            }
+            if let Some(limit) = self.idle_timeout {
+                pause = pause.min(limit - silent);
+            }
+            if finished.recv_timeout(pause) != Err(RecvTimeoutError::Timeout) {
+                // 子进程已退出：补写最后一次输出时间，随后由完成记录保留
+                self.beat(self.activity.snapshot().1);
+                return None;
+            }
+        }
     }
 
-    Ok(extract_exit_code(status))
+    /// 运行了 `elapsed`、已静默 `silent` 时是否触发超时，以及触发的时限
+    fn expiry(&self, elapsed: Duration, silent: Duration) -> Option<(Expiry, Duration)> {
+        if let Some(limit) = self.timeout
+            && elapsed >= limit
+        {
+            Some((Expiry::Timeout, limit))
+        } else if let Some(limit) = self.idle_timeout
+            && silent >= limit
+        {
+            Some((Expiry::Idle, limit))
+        } else {
+            None
+        }
+    }
+
+    /// 记录可能已被扫描方清理，写入失败时忽略
+    fn beat(&self, last_output_at: Option<DateTime<Utc>>) {
+        if let Some((registry, task_id)) = self.registration {
+            let _ = registry.heartbeat(task_id, Utc::now(), last_output_at);
+        }
+    }
+}
+
+/// 打印日志末尾的若干行，供 summary 模式在 Codex 退出后使用
+fn print_summary(log_path: &Path) -> io::Result<()> {
+    let mut file = File::open(log_path)?;
+    let offset = logs::tail_offset(&mut file, SUMMARY_LINES)?;
+    logs::copy_from(&mut file, offset)?;
+    Ok(())
 }
 
-fn generate_log_path(pid: u32) -> io::Result<PathBuf> {
+/// 第一次尝试使用 `{task_id}.log`，重试依次使用 `{task_id}.attempt{N}.log`
+fn generate_log_path(task_id: &str, attempt: u32) -> PathBuf {
     let tmp = std::env::temp_dir();
-    Ok(tmp.join(format!("{pid}.log")))
+    match attempt {
+        1 => tmp.join(format!("{task_id}.log")),
+        _ => tmp.join(format!("{task_id}.attempt{attempt}.log")),
+    }
 }
 
 #[derive(Copy, Clone)]
 enum StreamMirror {
+    None,
     Stdout,
     Stderr,
 }
@@ -153,6 +732,7 @@ enum StreamMirror {
 impl StreamMirror {
     fn write(self, data: &[u8]) -> io::Result<()> {
         match self {
+            StreamMirror::None => Ok(()),
             StreamMirror::Stdout => {
                 let mut handle = io::stdout().lock();
                 handle.write_all(data)?;
@@ -171,6 +751,7 @@ fn spawn_copy<R>(
     mut reader: R,
     writer: Arc<Mutex<BufWriter<std::fs::File>>>,
     mirror: StreamMirror,
+    activity: Arc<OutputActivity>,
 ) -> thread::JoinHandle<io::Result<()>>
 where
     R: Read + Send + 'static,
@@ -182,6 +763,7 @@ where
             if read == 0 {
                 break;
             }
+            activity.touch();
             let chunk = &buffer[..read];
             {
                 let mut guard = writer
@@ -202,15 +784,15 @@ fn extract_exit_code(status: ExitStatus) -> i32 {
 
 struct RegistrationGuard<'a> {
     registry: &'a TaskRegistry,
-    pid: u32,
+    task_id: String,
     active: bool,
 }
 
 impl<'a> RegistrationGuard<'a> {
-    fn new(registry: &'a TaskRegistry, pid: u32) -> Self {
+    fn new(registry: &'a TaskRegistry, task_id: String) -> Self {
         Self {
             registry,
-            pid,
+            task_id,
             active: true,
         }
     }
@@ -220,20 +802,96 @@ impl<'a> RegistrationGuard<'a> {
         result: Option<String>,
         exit_code: Option<i32>,
         completed_at: DateTime<Utc>,
-    ) -> Result<(), RegistryError> {
-        if self.active {
-            self.registry
-                .mark_completed(self.pid, result, exit_code, completed_at)?;
-            self.active = false;
-        }
-        Ok(())
+        stopped_by: Option<StopSignal>,
+    ) -> Result<TaskRecord, RegistryError> {
+        let record = self.registry.mark_completed(
+            &self.task_id,
+            result,
+            exit_code,
+            completed_at,
+            stopped_by,
+        )?;
+        self.active = false;
+        Ok(record)
+    }
+
+    fn record_attempt(&self, attempt: Attempt) {
+        let _ = self
+            .registry
+            .update(&self.task_id, |record| record.with_attempt(attempt));
+    }
+
+    /// 记录仍处于运行状态；被 `kill` 取消或被扫描方清理后不再重试
+    fn still_running(&self) -> bool {
+        matches!(
+            self.registry.find(&self.task_id),
+            Ok(Some(entry)) if entry.record.status == TaskStatus::Running
+        )
+    }
+
+    /// 保留已被取消的记录，交给 `wait` 读取
+    fn keep(mut self) {
+        self.active = false;
+    }
+}
+
+/// 占用的全局并发槽位，监督进程结束时释放并唤醒排队的任务
+struct SlotGuard<'a> {
+    registry: &'a TaskRegistry,
+    index: usize,
+    pid: u32,
+    start_time: Option<u64>,
+}
+
+impl Drop for SlotGuard<'_> {
+    fn drop(&mut self) {
+        self.registry
+            .release_slot(self.index, self.pid, self.start_time);
     }
 }
 
 impl Drop for RegistrationGuard<'_> {
     fn drop(&mut self) {
         if self.active {
-            let _ = self.registry.remove(self.pid);
+            let _ = self.registry.remove(&self.task_id);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn watchdog_expires_on_timeout_before_idle() {
+        let policy = EscalationPolicy::default();
+        let activity = OutputActivity::new();
+        let watchdog = Watchdog {
+            pid: 0,
+            timeout: Some(Duration::from_secs(60)),
+            idle_timeout: Some(Duration::from_secs(10)),
+            policy: &policy,
+            activity: &activity,
+            registration: None,
+        };
+        let secs = Duration::from_secs;
+        assert!(watchdog.expiry(secs(59), secs(9)).is_none());
+        assert!(matches!(
+            watchdog.expiry(secs(30), secs(10)),
+            Some((Expiry::Idle, limit)) if limit == secs(10)
+        ));
+        assert!(matches!(
+            watchdog.expiry(secs(60), secs(10)),
+            Some((Expiry::Timeout, limit)) if limit == secs(60)
+        ));
+        assert_eq!(Expiry::Timeout.result(), TIMED_OUT_RESULT);
+        assert_eq!(Expiry::Idle.result(), IDLE_TIMEOUT_RESULT);
+
+        let unlimited = Watchdog {
+            timeout: None,
+            idle_timeout: None,
+            ..watchdog
+        };
+        assert!(unlimited.expiry(secs(86400), secs(86400)).is_none());
+    }
+}
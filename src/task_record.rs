@@ -1,23 +1,64 @@
+use crate::config::DEFAULT_BACKEND;
+use crate::escalation::StopSignal;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum TaskStatus {
+    /// 等待全局并发槽位，尚未启动子进程（`pid` 为 0）
+    Queued,
     #[default]
     Running,
     CompletedButUnread,
 }
 
+impl TaskStatus {
+    pub fn is_finished(&self) -> bool {
+        *self == TaskStatus::CompletedButUnread
+    }
+}
+
+/// 启用 `--retries` 时的一次运行尝试
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attempt {
+    /// 从 1 开始
+    pub number: u32,
+    pub pid: u32,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub exit_code: Option<i32>,
+    pub result: Option<String>,
+    #[serde(default)]
+    pub stopped_by: Option<String>,
+    pub log_path: String,
+}
+
+fn default_backend() -> String {
+    DEFAULT_BACKEND.to_owned()
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TaskRecord {
+    /// 旧记录中缺失，读取时由注册表的键补齐
+    #[serde(default)]
+    pub pid: u32,
     pub started_at: DateTime<Utc>,
     pub log_id: String,
     pub log_path: String,
     #[serde(default)]
     pub manager_pid: Option<u32>,
+    /// 子进程与监督进程的启动时间（平台相关的单位），用于判断 PID 是否已被复用
+    #[serde(default)]
+    pub child_start_time: Option<u64>,
+    #[serde(default)]
+    pub manager_start_time: Option<u64>,
     #[serde(default)]
     pub cleanup_reason: Option<String>,
+    /// 最终让进程退出的信号，例如 `SIGTERM`；进程自行退出时为空
+    #[serde(default)]
+    pub stopped_by: Option<String>,
     #[serde(default)]
     pub status: TaskStatus,
     #[serde(default)]
@@ -26,26 +67,134 @@ pub struct TaskRecord {
     pub completed_at: Option<DateTime<Utc>>,
     #[serde(default)]
     pub exit_code: Option<i32>,
+    #[serde(default = "default_backend")]
+    pub backend: String,
+    #[serde(default)]
+    pub binary_path: Option<String>,
+    #[serde(default)]
+    pub binary_version: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// 子进程最近一次产生输出的时间，由监督进程定期写入
+    #[serde(default)]
+    pub last_output_at: Option<DateTime<Utc>>,
+    /// 监督进程定期写入的心跳；旧版本写入的记录没有该字段
+    #[serde(default)]
+    pub heartbeat_at: Option<DateTime<Utc>>,
+    /// 已结束的各次尝试，按顺序排列；未启用重试时为空
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<Attempt>,
 }
 
 impl TaskRecord {
     pub fn new(
+        pid: u32,
         started_at: DateTime<Utc>,
         log_id: String,
         log_path: String,
         manager_pid: Option<u32>,
     ) -> Self {
         Self {
+            pid,
             started_at,
             log_id,
             log_path,
             manager_pid,
+            child_start_time: None,
+            manager_start_time: None,
             cleanup_reason: None,
+            stopped_by: None,
             status: TaskStatus::Running,
             result: None,
             completed_at: None,
             exit_code: None,
+            backend: default_backend(),
+            binary_path: None,
+            binary_version: None,
+            tags: Vec::new(),
+            group: None,
+            cwd: None,
+            last_output_at: None,
+            heartbeat_at: None,
+            attempts: Vec::new(),
+        }
+    }
+
+    pub fn with_backend(mut self, backend: &str) -> Self {
+        self.backend = backend.to_owned();
+        self
+    }
+
+    pub fn with_binary(mut self, path: String, version: Option<String>) -> Self {
+        self.binary_path = Some(path);
+        self.binary_version = version;
+        self
+    }
+
+    pub fn with_start_times(mut self, child: Option<u64>, manager: Option<u64>) -> Self {
+        self.child_start_time = child;
+        self.manager_start_time = manager;
+        self
+    }
+
+    pub fn with_labels(mut self, tags: &[String], group: Option<&str>) -> Self {
+        self.tags = tags.to_vec();
+        self.group = group.map(str::to_owned);
+        self
+    }
+
+    pub fn with_cwd(mut self, cwd: Option<PathBuf>) -> Self {
+        self.cwd = cwd.map(|path| path.to_string_lossy().into_owned());
+        self
+    }
+
+    pub fn with_last_output_at(mut self, at: DateTime<Utc>) -> Self {
+        self.last_output_at = Some(at);
+        self
+    }
+
+    pub fn with_heartbeat(mut self, at: DateTime<Utc>) -> Self {
+        self.heartbeat_at = Some(at);
+        self
+    }
+
+    pub fn with_attempt(mut self, attempt: Attempt) -> Self {
+        self.attempts.push(attempt);
+        self
+    }
+
+    /// 等待全局并发槽位；此时 `started_at` 为入队时间
+    pub fn queued(mut self) -> Self {
+        self.status = TaskStatus::Queued;
+        self
+    }
+
+    /// 排队的任务获得槽位：从此刻起计算运行时间
+    pub fn dequeued(mut self, started_at: DateTime<Utc>) -> Self {
+        if self.status == TaskStatus::Queued {
+            self.status = TaskStatus::Running;
+            self.started_at = started_at;
         }
+        self
+    }
+
+    /// 子进程启动（排队结束或重试）：记录改为指向新的子进程与日志
+    pub fn with_child(mut self, pid: u32, start_time: Option<u64>, log_path: String) -> Self {
+        self.pid = pid;
+        self.child_start_time = start_time;
+        self.log_path = log_path;
+        self.last_output_at = None;
+        self
+    }
+
+    /// 运行中的任务距最近一次输出（尚无输出时为启动）过了多久
+    pub fn silent_for(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        (self.status == TaskStatus::Running)
+            .then(|| now.signed_duration_since(self.last_output_at.unwrap_or(self.started_at)))
     }
 
     pub fn mark_completed(
@@ -61,6 +210,14 @@ impl TaskRecord {
         self
     }
 
+    /// 只在确实发送过信号时写入，避免覆盖先前记录的结果
+    pub fn with_stopped_by(mut self, signal: Option<StopSignal>) -> Self {
+        if let Some(signal) = signal {
+            self.stopped_by = Some(signal.as_str().to_owned());
+        }
+        self
+    }
+
     pub fn with_cleanup_reason(mut self, reason: &str) -> Self {
         let result = self.result.clone();
         let exit_code = self.exit_code;
@@ -70,3 +227,15 @@ impl TaskRecord {
         self
     }
 }
+
+/// 任务是否成功结束：未被清理、退出码为 0 且结果不是 `failed_*`。
+/// 被信号终止的任务没有退出码，按失败处理
+pub fn is_success(
+    cleanup_reason: Option<&str>,
+    exit_code: Option<i32>,
+    result: Option<&str>,
+) -> bool {
+    cleanup_reason.is_none()
+        && exit_code == Some(0)
+        && result.is_none_or(|result| !result.starts_with("failed_"))
+}
@@ -1,14 +1,21 @@
-use crate::config::{
-    LEGACY_WAIT_INTERVAL_ENV, MAX_WAIT_DURATION, WAIT_INTERVAL_DEFAULT, WAIT_INTERVAL_ENV,
-};
-use crate::logging::warn;
+use crate::cli::{self, UsageError};
+use crate::config::{WAIT_EXIT_FAILED, WAIT_EXIT_TIMED_OUT};
+use crate::duration::parse_duration;
+use crate::escalation;
+use crate::history;
+use crate::i18n::{Lang, Messages, fill};
+use crate::logging::debug;
 use crate::platform;
-use crate::registry::{CleanupReason, RegistryEntry, RegistryError, TaskRegistry};
-use crate::task_record::{TaskRecord, TaskStatus};
+use crate::registry::{self, CleanupReason, RegistryEntry, RegistryError, TaskRegistry};
+use crate::settings;
+use crate::status::format_age;
+use crate::task_record::{self, Attempt, TaskRecord, TaskStatus};
 use chrono::{DateTime, Local, Utc};
-use std::collections::HashSet;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet};
+use std::ffi::OsString;
 use std::fmt::Write;
-use std::thread;
+use std::str::FromStr;
 use std::time::{Duration, Instant};
 use thiserror::Error;
 
@@ -16,103 +23,439 @@ use thiserror::Error;
 pub enum WaitError {
     #[error("registry error: {0}")]
     Registry(#[from] RegistryError),
+    #[error("{0}")]
+    Usage(#[from] UsageError),
+    #[error("serialization failed: {0}")]
+    Serialize(#[from] serde_json::Error),
+    #[error("no task found for {0}")]
+    NotFound(String),
 }
 
-pub fn run() -> Result<(), WaitError> {
+/// `wait` 的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum WaitFormat {
+    /// 带 emoji 的 Markdown 报告（默认）
+    #[default]
+    Markdown,
+    /// 与 Markdown 相同的内容，但不含标记与 emoji
+    Plain,
+    /// 结束时输出单个 JSON 对象
+    Json,
+    /// 每个完成事件一行 JSON，最后一行为汇总对象
+    Jsonl,
+}
+
+impl FromStr for WaitFormat {
+    type Err = UsageError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Ok(WaitFormat::Markdown),
+            "plain" | "text" => Ok(WaitFormat::Plain),
+            "json" => Ok(WaitFormat::Json),
+            "jsonl" | "ndjson" => Ok(WaitFormat::Jsonl),
+            _ => Err(UsageError(format!(
+                "invalid wait format {value}, expected json|jsonl|markdown|plain"
+            ))),
+        }
+    }
+}
+
+/// 选择 `wait` 关注的任务；未给出任何条件时匹配注册表中的全部任务
+#[derive(Debug, Default)]
+struct TaskFilter {
+    ids: Vec<String>,
+    tags: Vec<String>,
+    group: Option<String>,
+}
+
+impl TaskFilter {
+    /// 同类条件之间为“或”，不同类条件之间为“且”
+    fn matches(&self, record: &TaskRecord) -> bool {
+        let id_matches = self.ids.is_empty() || self.ids.iter().any(|id| id_matches(id, record));
+        let tag_matches =
+            self.tags.is_empty() || self.tags.iter().any(|tag| record.tags.contains(tag));
+        let group_matches = self
+            .group
+            .as_ref()
+            .is_none_or(|group| record.group.as_ref() == Some(group));
+        id_matches && tag_matches && group_matches
+    }
+
+    /// 第一个在 `entries` 中找不到任何任务的显式 id
+    fn unmatched_id(&self, entries: &[RegistryEntry]) -> Option<&str> {
+        self.ids
+            .iter()
+            .find(|id| !entries.iter().any(|entry| id_matches(id, &entry.record)))
+            .map(String::as_str)
+    }
+}
+
+/// 按任务 id 或 PID 匹配；排队中的任务还没有子进程，PID 为 0，只能按任务 id 匹配
+fn id_matches(id: &str, record: &TaskRecord) -> bool {
+    id == record.log_id
+        || (record.pid != 0 && record.status != TaskStatus::Queued && id.parse() == Ok(record.pid))
+}
+
+/// `wait` 在何时返回
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum WaitUntil {
+    /// 所有匹配的任务都结束
+    #[default]
+    All,
+    /// 读取到 N 个完成的任务（`--any` 即 N = 1）
+    First(usize),
+    /// 读取到第一个成功的任务
+    FirstSuccess,
+}
+
+impl WaitUntil {
+    fn satisfied(self, report: &TaskReport) -> bool {
+        match self {
+            WaitUntil::All => false,
+            WaitUntil::First(count) => report.total_count() >= count,
+            WaitUntil::FirstSuccess => report.successful_count() > 0,
+        }
+    }
+}
+
+/// 达到等待上限时如何处理仍在运行的任务
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum TimeoutAction {
+    /// 输出包含运行中任务的报告（默认）
+    #[default]
+    Report,
+    /// 终止其余匹配的任务并计入报告
+    Kill,
+    /// 保留任务继续运行，即使指定了 `--no-fail` 也以非零状态退出
+    Leave,
+}
+
+impl FromStr for TimeoutAction {
+    type Err = UsageError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "report" => Ok(TimeoutAction::Report),
+            "kill" => Ok(TimeoutAction::Kill),
+            "leave" => Ok(TimeoutAction::Leave),
+            _ => Err(UsageError(format!(
+                "invalid timeout action {value}, expected report|kill|leave"
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct WaitOptions {
+    format: WaitFormat,
+    lang: Option<Lang>,
+    no_fail: bool,
+    filter: TaskFilter,
+    until: WaitUntil,
+    cancel_rest: bool,
+    timeout: Option<Duration>,
+    interval: Option<Duration>,
+    timeout_action: TimeoutAction,
+}
+
+impl WaitOptions {
+    fn parse(args: &[String]) -> Result<Self, UsageError> {
+        let mut options = WaitOptions::default();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--format" => options.format = cli::take_value(arg, &mut iter)?.parse()?,
+                "--lang" => options.lang = Some(cli::take_value(arg, &mut iter)?.parse()?),
+                "--no-fail" => options.no_fail = true,
+                "--any" => options.until = WaitUntil::First(1),
+                "--first" => {
+                    let value = cli::take_value(arg, &mut iter)?;
+                    let count = value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|count| *count > 0)
+                        .ok_or_else(|| {
+                            UsageError(format!("--first expects a positive count, got {value}"))
+                        })?;
+                    options.until = WaitUntil::First(count);
+                }
+                "--first-success" => options.until = WaitUntil::FirstSuccess,
+                "--cancel-rest" => options.cancel_rest = true,
+                "--timeout" => options.timeout = Some(duration_value(arg, &mut iter)?),
+                "--interval" => {
+                    let interval = duration_value(arg, &mut iter)?;
+                    if interval.is_zero() {
+                        return Err(UsageError(
+                            "--interval must be greater than zero".to_owned(),
+                        ));
+                    }
+                    options.interval = Some(interval);
+                }
+                "--timeout-action" => {
+                    options.timeout_action = cli::take_value(arg, &mut iter)?.parse()?
+                }
+                "--tag" => options
+                    .filter
+                    .tags
+                    .push(cli::take_value(arg, &mut iter)?.to_owned()),
+                "--group" => {
+                    options.filter.group = Some(cli::take_value(arg, &mut iter)?.to_owned())
+                }
+                other => {
+                    if let Some(value) = other.strip_prefix("--format=") {
+                        options.format = value.parse()?;
+                    } else if let Some(value) = other.strip_prefix("--lang=") {
+                        options.lang = Some(value.parse()?);
+                    } else if other.starts_with('-') {
+                        return Err(cli::unknown_argument(other));
+                    } else {
+                        options.filter.ids.push(other.to_owned());
+                    }
+                }
+            }
+        }
+        if options.cancel_rest && options.until == WaitUntil::All {
+            return Err(UsageError(
+                "--cancel-rest requires --any, --first or --first-success".to_owned(),
+            ));
+        }
+        Ok(options)
+    }
+}
+
+fn duration_value<'a, I>(flag: &str, iter: &mut I) -> Result<Duration, UsageError>
+where
+    I: Iterator<Item = &'a String>,
+{
+    let value = cli::take_value(flag, iter)?;
+    parse_duration(value).map_err(|err| UsageError(format!("{flag}: {err}")))
+}
+
+/// 返回进程退出码：全部成功为 0，有失败为 2，超时仍有任务运行为 3；`--no-fail` 时始终为 0
+pub fn run(args: &[OsString]) -> Result<i32, WaitError> {
+    let options = WaitOptions::parse(&cli::to_strings(args)?)?;
+    let format = options.format;
     let registry = TaskRegistry::connect()?;
-    let interval = read_interval();
+    let settings = settings::get();
+    let style = Style {
+        markdown: format == WaitFormat::Markdown,
+        msg: options.lang.unwrap_or(settings.lang.value).messages(),
+    };
+    let interval = options.interval.unwrap_or(settings.wait_interval.value);
+    let timeout = options.timeout.unwrap_or(settings.max_wait_duration.value);
     let start = Instant::now();
-    let mut processed_pids: HashSet<u32> = HashSet::new();
+    let mut processed: HashSet<String> = HashSet::new();
     let mut report = TaskReport::new();
 
+    // 拼错或已被读走的 id 不能当作“没有任务在运行”而成功返回
+    if let Some(id) = options.filter.unmatched_id(&registry.entries()?) {
+        return Err(WaitError::NotFound(id.to_owned()));
+    }
+
     loop {
+        let generation = registry.generation();
+        // 清理只会把失效的任务标记为已完成，结果统一在下面按过滤条件读取
         let now = chrono::Utc::now();
         let cleanups = registry.sweep_stale_entries(
             now,
-            platform::process_alive,
-            &platform::terminate_process,
+            platform::same_process_alive,
+            &escalation::stop_task,
         )?;
         for event in cleanups {
-            if event.reason == CleanupReason::Timeout {
+            debug(format!(
+                "sweep cleaned up pid={} ({}): {}",
+                event._pid,
+                event.record.log_path,
+                event.reason.as_str()
+            ));
+        }
+
+        for entry in registry.get_completed_unread_tasks()? {
+            if !options.filter.matches(&entry.record) {
                 continue;
             }
-            let pid = event._pid;
-            if processed_pids.insert(pid) {
-                let completion = TaskCompletion::from_record(pid, event.record);
-                emit_realtime_update(&completion);
+            let _ = registry.remove(&entry.task_id)?;
+            if processed.insert(entry.task_id) {
+                let completion = TaskCompletion::from_record(entry.record);
+                emit_realtime_update(&completion, format, style)?;
                 report.add_completion(completion);
             }
         }
 
-        for (pid, record) in registry.get_completed_unread_tasks()? {
-            if processed_pids.insert(pid) {
-                let completion = TaskCompletion::from_record(pid, record);
-                emit_realtime_update(&completion);
-                report.add_completion(completion);
+        if options.until.satisfied(&report) {
+            let code = match options.until {
+                WaitUntil::FirstSuccess => 0,
+                _ => exit_code(&report, false, options.no_fail),
+            };
+            if options.cancel_rest {
+                cancel_remaining(
+                    &registry,
+                    &options.filter,
+                    CleanupReason::CancelledByUser,
+                    &mut report,
+                    format,
+                    style,
+                )?;
             }
-            let _ = registry.remove_by_pid(pid)?;
+            print_report(&report, None, &[], false, start.elapsed(), format, style)?;
+            return Ok(code);
         }
 
-        let entries = registry.entries()?;
+        // 排队位置按全部任务计算，而不只是匹配过滤条件的任务
+        let all_entries = registry.entries()?;
+        let entries: Vec<RegistryEntry> = all_entries
+            .iter()
+            .filter(|entry| options.filter.matches(&entry.record))
+            .cloned()
+            .collect();
         let has_running = entries
             .iter()
-            .any(|entry| entry.record.status == TaskStatus::Running);
+            .any(|entry| !entry.record.status.is_finished());
 
         if !has_running {
-            print_report(&report, None, false, start.elapsed());
-            return Ok(());
+            print_report(&report, None, &[], false, start.elapsed(), format, style)?;
+            return Ok(exit_code(&report, false, options.no_fail));
         }
 
-        if start.elapsed() >= MAX_WAIT_DURATION {
-            print_report(&report, Some(&entries), true, start.elapsed());
-            return Ok(());
+        let elapsed = start.elapsed();
+        if elapsed >= timeout {
+            let running = match options.timeout_action {
+                TimeoutAction::Kill => {
+                    cancel_remaining(
+                        &registry,
+                        &options.filter,
+                        CleanupReason::WaitTimeout,
+                        &mut report,
+                        format,
+                        style,
+                    )?;
+                    None
+                }
+                TimeoutAction::Report | TimeoutAction::Leave => Some(entries.as_slice()),
+            };
+            print_report(&report, running, &all_entries, true, elapsed, format, style)?;
+            let no_fail = options.no_fail && options.timeout_action != TimeoutAction::Leave;
+            return Ok(exit_code(&report, true, no_fail));
         }
 
-        thread::sleep(interval);
+        // 任务结束时监督进程会发出通知；间隔只作为兜底（例如进程崩溃未能通知）
+        registry.wait_for_change(generation, interval.min(timeout - elapsed));
     }
 }
 
-fn read_interval() -> Duration {
-    read_env_interval(WAIT_INTERVAL_ENV)
-        .or_else(|| read_env_interval(LEGACY_WAIT_INTERVAL_ENV))
-        .unwrap_or(WAIT_INTERVAL_DEFAULT)
+/// 终止其余匹配的运行中任务，并以给定的清理原因计入报告
+fn cancel_remaining(
+    registry: &TaskRegistry,
+    filter: &TaskFilter,
+    reason: CleanupReason,
+    report: &mut TaskReport,
+    format: WaitFormat,
+    style: Style,
+) -> Result<(), WaitError> {
+    for entry in registry.entries()? {
+        if entry.record.status.is_finished() || !filter.matches(&entry.record) {
+            continue;
+        }
+        let record = registry.cancel(
+            &entry.task_id,
+            reason,
+            platform::same_process_alive,
+            &escalation::stop_task,
+        )?;
+        let _ = registry.remove(&entry.task_id)?;
+        // 记录已被移除，监督进程之后无法再写入历史，由这里补上
+        history::append(&record);
+        let completion = TaskCompletion::from_record(record);
+        emit_realtime_update(&completion, format, style)?;
+        report.add_completion(completion);
+    }
+    Ok(())
 }
 
-fn read_env_interval(var: &str) -> Option<Duration> {
-    match std::env::var(var) {
-        Ok(raw) => match raw.parse::<u64>() {
-            Ok(seconds) if seconds > 0 => Some(Duration::from_secs(seconds)),
-            _ => {
-                warn(format!(
-                    "environment variable {var} invalid, using default 30s"
-                ));
-                None
-            }
-        },
-        Err(_) => None,
+fn exit_code(report: &TaskReport, timed_out: bool, no_fail: bool) -> i32 {
+    if no_fail {
+        0
+    } else if timed_out {
+        WAIT_EXIT_TIMED_OUT
+    } else if report.successful_count() < report.total_count() {
+        WAIT_EXIT_FAILED
+    } else {
+        0
     }
 }
 
-fn emit_realtime_update(task: &TaskCompletion) {
+/// jsonl 输出中的一行，通过 `event` 字段区分
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum WaitEvent<'a> {
+    Completion(&'a TaskCompletion),
+    Summary(WaitSummary<'a>),
+}
+
+/// 机器可读的最终汇总；json 格式下附带全部完成记录
+#[derive(Serialize)]
+struct WaitSummary<'a> {
+    timed_out: bool,
+    total: usize,
+    succeeded: usize,
+    failed: usize,
+    cancelled: usize,
+    /// 秒
+    wait_elapsed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    completions: Option<&'a [TaskCompletion]>,
+    running: Vec<RunningTask<'a>>,
+}
+
+#[derive(Serialize)]
+struct RunningTask<'a> {
+    task_id: &'a str,
+    status: &'a TaskStatus,
+    /// 仅排队中的任务有值，从 1 开始
+    #[serde(skip_serializing_if = "Option::is_none")]
+    queue_position: Option<usize>,
+    pid: u32,
+    log_path: &'a str,
+    backend: &'a str,
+    started_at: DateTime<Utc>,
+    last_output_at: Option<DateTime<Utc>>,
+    silent_secs: i64,
+}
+
+fn emit_realtime_update(
+    task: &TaskCompletion,
+    format: WaitFormat,
+    style: Style,
+) -> Result<(), WaitError> {
+    match format {
+        WaitFormat::Json => return Ok(()),
+        WaitFormat::Jsonl => {
+            println!("{}", serde_json::to_string(&WaitEvent::Completion(task))?);
+            return Ok(());
+        }
+        WaitFormat::Markdown | WaitFormat::Plain => {}
+    }
+    let msg = style.msg;
     let exit_code = task
         .exit_code
         .map(|code| code.to_string())
-        .unwrap_or_else(|| "未提供".to_string());
-    let status_word = if task.is_success() {
-        "完成"
-    } else {
-        "失败"
-    };
+        .unwrap_or_else(|| msg.not_provided.to_string());
     let header = format!(
-        "{} 任务{} PID={} (exit_code: {}) @ {}",
-        task.status_icon(),
-        status_word,
-        task.pid,
-        exit_code,
-        task.completed_time_local()
+        "{}{}",
+        style.icon(task.status_icon()),
+        fill(
+            msg.task_event,
+            &[
+                ("status", &task.status_word(msg)),
+                ("pid", &task.pid),
+                ("exit_code", &exit_code),
+                ("time", &task.completed_time_local()),
+            ],
+        )
     );
-    let log_line = format!("日志文件: {}", task.log_path);
-    let summary_line = format!("{}: {}", task.summary_label(), task.summary_text());
+    let log_line = format!("{}: {}", msg.label_log_file, task.log_path);
+    let summary_line = format!("{}: {}", task.summary_label(msg), task.summary_text(msg));
 
     if task.is_success() {
         println!("{header}");
@@ -123,53 +466,140 @@ fn emit_realtime_update(task: &TaskCompletion) {
         eprintln!("{log_line}");
         eprintln!("{summary_line}");
     }
+    Ok(())
 }
 
 fn print_report(
     report: &TaskReport,
     running_entries: Option<&[RegistryEntry]>,
+    queue: &[RegistryEntry],
     timed_out: bool,
     wait_elapsed: Duration,
-) {
-    let mut buffer = String::new();
-    report
-        .render(&mut buffer, running_entries, timed_out, wait_elapsed)
-        .expect("rendering wait report");
-    println!("{buffer}");
+    format: WaitFormat,
+    style: Style,
+) -> Result<(), WaitError> {
+    match format {
+        WaitFormat::Json | WaitFormat::Jsonl => {
+            let summary = report.summary(
+                running_entries.unwrap_or_default(),
+                queue,
+                timed_out,
+                wait_elapsed,
+                format == WaitFormat::Json,
+            );
+            let line = if format == WaitFormat::Json {
+                serde_json::to_string_pretty(&summary)?
+            } else {
+                serde_json::to_string(&WaitEvent::Summary(summary))?
+            };
+            println!("{line}");
+        }
+        WaitFormat::Markdown | WaitFormat::Plain => {
+            let mut buffer = String::new();
+            report
+                .render(
+                    &mut buffer,
+                    running_entries,
+                    queue,
+                    timed_out,
+                    wait_elapsed,
+                    style,
+                )
+                .expect("rendering wait report");
+            println!("{buffer}");
+        }
+    }
+    Ok(())
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize)]
 struct TaskCompletion {
+    task_id: String,
     pid: u32,
     log_path: String,
     started_at: DateTime<Utc>,
     completed_at: DateTime<Utc>,
+    /// 秒
+    duration: i64,
     exit_code: Option<i32>,
     result: Option<String>,
     cleanup_reason: Option<String>,
+    stopped_by: Option<String>,
+    backend: String,
+    binary_path: Option<String>,
+    binary_version: Option<String>,
+    tags: Vec<String>,
+    group: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    attempts: Vec<Attempt>,
 }
 
 impl TaskCompletion {
-    fn from_record(pid: u32, mut record: TaskRecord) -> Self {
+    fn from_record(mut record: TaskRecord) -> Self {
         let completed_at = record.completed_at.unwrap_or_else(Utc::now);
         record.completed_at = Some(completed_at);
         Self {
-            pid,
+            task_id: record.log_id,
+            pid: record.pid,
             log_path: record.log_path,
             started_at: record.started_at,
             completed_at,
+            duration: completed_at
+                .signed_duration_since(record.started_at)
+                .num_seconds()
+                .max(0),
             exit_code: record.exit_code,
             result: record.result,
             cleanup_reason: record.cleanup_reason,
+            stopped_by: record.stopped_by,
+            backend: record.backend,
+            binary_path: record.binary_path,
+            binary_version: record.binary_version,
+            tags: record.tags,
+            group: record.group,
+            attempts: record.attempts,
+        }
+    }
+
+    fn binary_label(&self) -> Option<String> {
+        match (&self.binary_version, &self.binary_path) {
+            (Some(version), Some(path)) => Some(format!("{version} ({path})")),
+            (Some(version), None) => Some(version.clone()),
+            (None, Some(path)) => Some(path.clone()),
+            (None, None) => None,
         }
     }
 
     fn is_success(&self) -> bool {
-        self.cleanup_reason.is_none() && self.exit_code.unwrap_or(0) == 0
+        task_record::is_success(
+            self.cleanup_reason.as_deref(),
+            self.exit_code,
+            self.result.as_deref(),
+        )
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cleanup_reason.as_deref() == Some(CleanupReason::CancelledByUser.as_str())
     }
 
     fn status_icon(&self) -> &'static str {
-        if self.is_success() { "✅" } else { "❌" }
+        if self.is_success() {
+            "✅"
+        } else if self.is_cancelled() {
+            "⛔"
+        } else {
+            "❌"
+        }
+    }
+
+    fn status_word(&self, msg: &'static Messages) -> &'static str {
+        if self.is_success() {
+            msg.word_completed
+        } else if self.is_cancelled() {
+            msg.word_cancelled
+        } else {
+            msg.word_failed
+        }
     }
 
     fn completed_time_local(&self) -> String {
@@ -179,23 +609,25 @@ impl TaskCompletion {
             .to_string()
     }
 
-    fn summary_label(&self) -> &'static str {
+    fn summary_label(&self, msg: &'static Messages) -> &'static str {
         if self.is_success() {
-            "结果摘要"
+            msg.label_result
         } else {
-            "错误摘要"
+            msg.label_error
         }
     }
 
-    fn summary_text(&self) -> String {
-        if let Some(result) = &self.result {
+    fn summary_text(&self, msg: &Messages) -> String {
+        if self.is_cancelled() {
+            msg.summary_cancelled.to_string()
+        } else if let Some(result) = &self.result {
             result.clone()
         } else if let Some(reason) = &self.cleanup_reason {
-            format!("任务被清理: {reason}")
+            fill(msg.summary_cleaned_up, &[("reason", reason)])
         } else if self.is_success() {
-            "任务成功完成，但未提供摘要。".to_string()
+            msg.summary_success_empty.to_string()
         } else {
-            "任务失败，未提供错误摘要。".to_string()
+            msg.summary_failure_empty.to_string()
         }
     }
 }
@@ -218,13 +650,13 @@ impl TaskReport {
     fn add_completion(&mut self, completion: TaskCompletion) {
         if self
             .earliest_start
-            .map_or(true, |current| completion.started_at < current)
+            .is_none_or(|current| completion.started_at < current)
         {
             self.earliest_start = Some(completion.started_at);
         }
         if self
             .latest_completion
-            .map_or(true, |current| completion.completed_at > current)
+            .is_none_or(|current| completion.completed_at > current)
         {
             self.latest_completion = Some(completion.completed_at);
         }
@@ -239,8 +671,16 @@ impl TaskReport {
         self.completions.iter().filter(|c| c.is_success()).count()
     }
 
+    fn backends(&self) -> BTreeSet<String> {
+        self.completions.iter().map(|c| c.backend.clone()).collect()
+    }
+
+    fn cancelled_count(&self) -> usize {
+        self.completions.iter().filter(|c| c.is_cancelled()).count()
+    }
+
     fn failed_count(&self) -> usize {
-        self.total_count() - self.successful_count()
+        self.total_count() - self.successful_count() - self.cancelled_count()
     }
 
     fn total_duration(&self) -> Option<chrono::Duration> {
@@ -250,42 +690,127 @@ impl TaskReport {
         }
     }
 
+    fn summary<'a>(
+        &'a self,
+        running_entries: &'a [RegistryEntry],
+        queue: &[RegistryEntry],
+        timed_out: bool,
+        wait_elapsed: Duration,
+        with_completions: bool,
+    ) -> WaitSummary<'a> {
+        let now = Utc::now();
+        WaitSummary {
+            timed_out,
+            total: self.total_count(),
+            succeeded: self.successful_count(),
+            failed: self.failed_count(),
+            cancelled: self.cancelled_count(),
+            wait_elapsed: wait_elapsed.as_secs(),
+            completions: with_completions.then_some(self.completions.as_slice()),
+            running: running_entries
+                .iter()
+                .filter(|entry| !entry.record.status.is_finished())
+                .map(|entry| RunningTask {
+                    task_id: &entry.task_id,
+                    status: &entry.record.status,
+                    queue_position: registry::queue_position(queue, &entry.task_id),
+                    pid: entry.pid,
+                    log_path: &entry.record.log_path,
+                    backend: &entry.record.backend,
+                    started_at: entry.record.started_at,
+                    last_output_at: entry.record.last_output_at,
+                    silent_secs: entry
+                        .record
+                        .silent_for(now)
+                        .map_or(0, |silent| silent.num_seconds()),
+                })
+                .collect(),
+        }
+    }
+
     fn render(
         &self,
         buffer: &mut String,
         running_entries: Option<&[RegistryEntry]>,
+        queue: &[RegistryEntry],
         timed_out: bool,
         wait_elapsed: Duration,
+        style: Style,
     ) -> Result<(), std::fmt::Error> {
-        writeln!(buffer, "## 📋 任务执行完成报告")?;
+        let msg = style.msg;
+        writeln!(buffer, "{}", style.heading(2, "📋", msg.report_title))?;
         if timed_out {
-            writeln!(buffer, "\n⚠️ 等待已达到最大时长，仍检测到未完成的任务。")?;
+            writeln!(buffer, "\n{}{}", style.icon("⚠️"), msg.report_timed_out)?;
         }
 
-        writeln!(buffer, "\n### ✅ 已完成任务列表")?;
+        writeln!(
+            buffer,
+            "\n{}",
+            style.heading(3, "✅", msg.completed_heading)
+        )?;
         if self.completions.is_empty() {
-            writeln!(buffer, "- 暂无完成任务")?;
+            writeln!(buffer, "- {}", msg.no_completed)?;
         } else {
             let mut items = self.completions.clone();
-            items.sort_by_key(|item| item.completed_at);
-            for (idx, completion) in items.iter().enumerate() {
-                writeln!(buffer, "{}. **PID**: {}", idx + 1, completion.pid)?;
+            items.sort_by(|a, b| {
+                a.backend
+                    .cmp(&b.backend)
+                    .then(a.completed_at.cmp(&b.completed_at))
+            });
+            let grouped = self.backends().len() > 1;
+            let mut current_backend: Option<&str> = None;
+            let mut idx = 0;
+            for completion in &items {
+                if grouped && current_backend != Some(completion.backend.as_str()) {
+                    current_backend = Some(completion.backend.as_str());
+                    idx = 0;
+                    writeln!(buffer, "\n{}", style.heading(4, "🤖", &completion.backend))?;
+                }
+                idx += 1;
+                writeln!(buffer, "{}. {}: {}", idx, style.bold("PID"), completion.pid)?;
+                writeln!(
+                    buffer,
+                    "   - {}: {}",
+                    style.bold(msg.label_status),
+                    completion.status_with_exit_code(style)
+                )?;
                 writeln!(
                     buffer,
-                    "   - **状态**: {}",
-                    completion.status_icon_with_exit_code()
+                    "   - {}: {}",
+                    style.bold(msg.label_log_file),
+                    completion.log_path
                 )?;
-                writeln!(buffer, "   - **日志文件**: {}", completion.log_path)?;
+                if let Some(binary) = completion.binary_label() {
+                    writeln!(buffer, "   - {}: {binary}", style.bold(msg.label_version))?;
+                }
                 writeln!(
                     buffer,
-                    "   - **完成时间**: {}",
+                    "   - {}: {}",
+                    style.bold(msg.label_completed_at),
                     completion.completed_time_local()
                 )?;
+                if completion.attempts.len() > 1 {
+                    writeln!(
+                        buffer,
+                        "   - {}: {}",
+                        style.bold(msg.label_attempts),
+                        completion.attempts.len()
+                    )?;
+                    for attempt in &completion.attempts {
+                        writeln!(
+                            buffer,
+                            "     {}. {} -> {}",
+                            attempt.number,
+                            attempt.result.as_deref().unwrap_or(msg.not_provided),
+                            attempt.log_path
+                        )?;
+                    }
+                }
                 writeln!(
                     buffer,
-                    "   - **{}**: {}",
-                    completion.summary_label(),
-                    completion.summary_text()
+                    "   - {}: {}",
+                    style.bold(completion.summary_label(msg)),
+                    completion.summary_text(msg)
                 )?;
             }
         }
@@ -294,22 +819,59 @@ impl TaskReport {
             .total_duration()
             .or_else(|| chrono::Duration::from_std(wait_elapsed).ok())
             .unwrap_or_else(chrono::Duration::zero);
-        writeln!(buffer, "\n### 📊 执行统计")?;
-        writeln!(buffer, "- 总任务数: {}", self.total_count())?;
-        writeln!(buffer, "- 成功: {}个", self.successful_count())?;
-        writeln!(buffer, "- 失败: {}个", self.failed_count())?;
+        writeln!(buffer, "\n{}", style.heading(3, "📊", msg.stats_heading))?;
+        let counts = [
+            (msg.stats_total, self.total_count()),
+            (msg.stats_succeeded, self.successful_count()),
+            (msg.stats_failed, self.failed_count()),
+            (msg.stats_cancelled, self.cancelled_count()),
+        ];
+        for (template, count) in counts {
+            writeln!(buffer, "- {}", fill(template, &[("count", &count)]))?;
+        }
+        let backends = self.backends();
+        if backends.len() > 1 {
+            let counts: Vec<String> = backends
+                .iter()
+                .map(|backend| {
+                    let count = self
+                        .completions
+                        .iter()
+                        .filter(|c| &c.backend == backend)
+                        .count();
+                    fill(
+                        msg.stats_backend_count,
+                        &[("backend", backend), ("count", &count)],
+                    )
+                })
+                .collect();
+            writeln!(
+                buffer,
+                "- {}",
+                fill(msg.stats_by_backend, &[("counts", &counts.join(", "))])
+            )?;
+        }
         writeln!(
             buffer,
-            "- 总耗时: {}",
-            format_human_duration(total_duration)
+            "- {}",
+            fill(
+                msg.stats_elapsed,
+                &[("duration", &format_human_duration(total_duration, msg))]
+            )
         )?;
 
-        writeln!(buffer, "\n### 📂 完整日志文件路径")?;
+        writeln!(buffer, "\n{}", style.heading(3, "📂", msg.logs_heading))?;
         let mut log_paths: Vec<String> = Vec::new();
         if self.completions.is_empty() {
-            writeln!(buffer, "- 无可用日志")?;
+            writeln!(buffer, "- {}", msg.no_logs)?;
         } else {
-            let mut paths: Vec<&String> = self.completions.iter().map(|c| &c.log_path).collect();
+            let mut paths: Vec<&String> = self
+                .completions
+                .iter()
+                .flat_map(|c| {
+                    std::iter::once(&c.log_path).chain(c.attempts.iter().map(|a| &a.log_path))
+                })
+                .collect();
             paths.sort();
             paths.dedup();
             for path in &paths {
@@ -321,85 +883,140 @@ impl TaskReport {
         if let Some(entries) = running_entries {
             let running: Vec<&RegistryEntry> = entries
                 .iter()
-                .filter(|entry| entry.record.status == TaskStatus::Running)
+                .filter(|entry| !entry.record.status.is_finished())
                 .collect();
             if !running.is_empty() {
-                writeln!(buffer, "\n### ⏳ 仍在运行的任务")?;
+                let now = Utc::now();
+                writeln!(buffer, "\n{}", style.heading(3, "⏳", msg.running_heading))?;
                 for entry in running {
+                    if let Some(position) = registry::queue_position(queue, &entry.task_id) {
+                        writeln!(
+                            buffer,
+                            "- {}",
+                            fill(
+                                msg.queued_entry,
+                                &[
+                                    ("task_id", &entry.task_id),
+                                    ("position", &position),
+                                    ("log_path", &entry.record.log_path),
+                                ],
+                            )
+                        )?;
+                        continue;
+                    }
                     let started = entry
                         .record
                         .started_at
                         .with_timezone(&Local)
                         .format("%Y-%m-%d %H:%M:%S");
+                    let silent = entry
+                        .record
+                        .silent_for(now)
+                        .map_or_else(|| "-".to_owned(), format_age);
                     writeln!(
                         buffer,
-                        "- PID {} (启动于 {started}) -> {}",
-                        entry.pid, entry.record.log_path
+                        "- {}",
+                        fill(
+                            msg.running_entry,
+                            &[
+                                ("pid", &entry.pid),
+                                ("started", &started),
+                                ("silent", &silent),
+                                ("log_path", &entry.record.log_path),
+                            ],
+                        )
                     )?;
                 }
             }
         }
 
+        writeln!(buffer, "\n{}", msg.continue_hint)?;
+        writeln!(buffer, "\n{}", style.heading(3, "🧠", msg.reading_heading))?;
+        let offset_limit: [(&str, &dyn std::fmt::Display); 2] = [
+            ("offset", &style.code("offset")),
+            ("limit", &style.code("limit")),
+        ];
+        writeln!(buffer, "- {}", msg.reading_batches)?;
         writeln!(
             buffer,
-            "\n现在请基于上述结果继续你的工作，必要时查看日志文件。"
-        )?;
-        writeln!(buffer, "\n### 🧠 Claude 日志阅读提示")?;
-        writeln!(
-            buffer,
-            "- Claude，请分批次读取体积较大的日志文件，避免一次性请求全部内容。"
-        )?;
-        writeln!(
-            buffer,
-            "- 请在读取日志时使用 `offset`/`limit` 参数来控制输出范围，逐段检查关键信息。"
+            "- {}",
+            fill(msg.reading_offset_limit, &offset_limit)
         )?;
         if log_paths.is_empty() {
-            writeln!(buffer, "- 当前没有可供阅读的日志文件路径，可在任务完成后再尝试。")?;
+            writeln!(buffer, "- {}", msg.reading_no_paths)?;
         } else {
-            writeln!(buffer, "- 建议按照以下路径逐个读取日志：")?;
+            writeln!(buffer, "- {}", msg.reading_paths)?;
             for path in &log_paths {
                 writeln!(buffer, "  - {path}")?;
             }
         }
-        writeln!(
-            buffer,
-            "- 读取完一批内容后，请说明下一步需要的 `offset`/`limit` 或指出新的文件路径，以便继续协助你。"
-        )?;
+        writeln!(buffer, "- {}", fill(msg.reading_next, &offset_limit))?;
         Ok(())
     }
 }
 
+/// 人类可读报告的呈现方式：Markdown 或纯文本，以及使用的语言
+#[derive(Clone, Copy)]
+struct Style {
+    markdown: bool,
+    msg: &'static Messages,
+}
+
+impl Style {
+    fn heading(self, level: usize, icon: &str, title: &str) -> String {
+        if self.markdown {
+            format!("{} {icon} {title}", "#".repeat(level))
+        } else {
+            title.to_owned()
+        }
+    }
+
+    fn bold(self, text: &str) -> String {
+        if self.markdown {
+            format!("**{text}**")
+        } else {
+            text.to_owned()
+        }
+    }
+
+    fn code(self, text: &str) -> String {
+        if self.markdown {
+            format!("`{text}`")
+        } else {
+            text.to_owned()
+        }
+    }
+
+    /// emoji 后附带一个空格，纯文本下为空
+    fn icon(self, icon: &str) -> String {
+        if self.markdown {
+            format!("{icon} ")
+        } else {
+            String::new()
+        }
+    }
+}
+
 impl TaskCompletion {
-    fn status_icon_with_exit_code(&self) -> String {
+    fn status_with_exit_code(&self, style: Style) -> String {
         let exit_code = self
             .exit_code
             .map(|code| code.to_string())
-            .unwrap_or_else(|| "未提供".to_string());
+            .unwrap_or_else(|| style.msg.not_provided.to_string());
+        let status = format!(
+            "{}{}",
+            style.icon(self.status_icon()),
+            self.status_word(style.msg)
+        );
         if let Some(reason) = &self.cleanup_reason {
-            format!(
-                "{} {} (exit_code: {exit_code}, cleanup: {reason})",
-                self.status_icon(),
-                if self.is_success() {
-                    "完成"
-                } else {
-                    "失败"
-                }
-            )
+            format!("{status} (exit_code: {exit_code}, cleanup: {reason})")
         } else {
-            format!(
-                "{} {} (exit_code: {exit_code})",
-                self.status_icon(),
-                if self.is_success() {
-                    "完成"
-                } else {
-                    "失败"
-                }
-            )
+            format!("{status} (exit_code: {exit_code})")
         }
     }
 }
 
-fn format_human_duration(duration: chrono::Duration) -> String {
+fn format_human_duration(duration: chrono::Duration, msg: &Messages) -> String {
     let mut seconds = duration.num_seconds();
     if seconds < 0 {
         seconds = 0;
@@ -410,63 +1027,177 @@ fn format_human_duration(duration: chrono::Duration) -> String {
 
     let mut parts = Vec::new();
     if hours > 0 {
-        parts.push(format!("{hours}小时"));
+        parts.push(format!("{hours}{}", msg.unit_hours));
     }
     if minutes > 0 {
-        parts.push(format!("{minutes}分"));
+        parts.push(format!("{minutes}{}", msg.unit_minutes));
     }
     if remaining_seconds > 0 || parts.is_empty() {
-        parts.push(format!("{remaining_seconds}秒"));
+        parts.push(format!("{remaining_seconds}{}", msg.unit_seconds));
     }
 
-    parts.join("")
+    parts.join(msg.unit_separator)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::env;
-    use std::sync::Mutex;
 
-    static ENV_GUARD: Mutex<()> = Mutex::new(());
+    #[test]
+    fn parses_format_flag() {
+        let args = ["--format".to_owned(), "jsonl".to_owned()];
+        assert_eq!(WaitOptions::parse(&args).unwrap().format, WaitFormat::Jsonl);
+        let args = ["--format=plain".to_owned()];
+        assert_eq!(WaitOptions::parse(&args).unwrap().format, WaitFormat::Plain);
+        assert!(WaitOptions::parse(&["--format".to_owned(), "xml".to_owned()]).is_err());
+    }
 
-    fn clear_env() {
-        unsafe {
-            env::remove_var(WAIT_INTERVAL_ENV);
-            env::remove_var(LEGACY_WAIT_INTERVAL_ENV);
-        }
+    #[test]
+    fn cancel_rest_requires_early_return() {
+        assert!(WaitOptions::parse(&["--cancel-rest".to_owned()]).is_err());
+        let args = [
+            "--first".to_owned(),
+            "2".to_owned(),
+            "--cancel-rest".to_owned(),
+        ];
+        let options = WaitOptions::parse(&args).unwrap();
+        assert_eq!(options.until, WaitUntil::First(2));
+        assert!(options.cancel_rest);
+        assert!(WaitOptions::parse(&["--first".to_owned(), "0".to_owned()]).is_err());
     }
 
     #[test]
-    fn prefers_primary_interval_env() {
-        let _lock = ENV_GUARD.lock().unwrap();
-        clear_env();
-        unsafe {
-            env::set_var(WAIT_INTERVAL_ENV, "45");
-        }
-        assert_eq!(read_interval(), Duration::from_secs(45));
-        clear_env();
+    fn parses_timeout_options() {
+        let args = [
+            "--timeout".to_owned(),
+            "5m".to_owned(),
+            "--interval".to_owned(),
+            "250ms".to_owned(),
+            "--timeout-action".to_owned(),
+            "kill".to_owned(),
+        ];
+        let options = WaitOptions::parse(&args).unwrap();
+        assert_eq!(options.timeout, Some(Duration::from_secs(300)));
+        assert_eq!(options.interval, Some(Duration::from_millis(250)));
+        assert_eq!(options.timeout_action, TimeoutAction::Kill);
+        assert!(WaitOptions::parse(&["--interval".to_owned(), "0s".to_owned()]).is_err());
     }
 
     #[test]
-    fn falls_back_to_legacy_env() {
-        let _lock = ENV_GUARD.lock().unwrap();
-        clear_env();
-        unsafe {
-            env::set_var(LEGACY_WAIT_INTERVAL_ENV, "90");
-        }
-        assert_eq!(read_interval(), Duration::from_secs(90));
-        clear_env();
+    fn filter_combines_selector_kinds() {
+        let record = TaskRecord::new(
+            42,
+            Utc::now(),
+            "task-42".to_owned(),
+            "/tmp/task-42.log".to_owned(),
+            None,
+        )
+        .with_labels(&["lint".to_owned()], Some("pr-1"));
+        assert!(TaskFilter::default().matches(&record));
+
+        let by_pid = TaskFilter {
+            ids: vec!["7".to_owned(), "42".to_owned()],
+            ..TaskFilter::default()
+        };
+        assert!(by_pid.matches(&record));
+
+        let by_task_id = TaskFilter {
+            ids: vec!["task-42".to_owned()],
+            ..TaskFilter::default()
+        };
+        assert!(by_task_id.matches(&record));
+
+        let tag_and_group = TaskFilter {
+            tags: vec!["lint".to_owned(), "test".to_owned()],
+            group: Some("pr-2".to_owned()),
+            ..TaskFilter::default()
+        };
+        assert!(!tag_and_group.matches(&record));
+
+        let entry = |record: TaskRecord| RegistryEntry {
+            pid: record.pid,
+            task_id: record.log_id.clone(),
+            record,
+        };
+        let queued = TaskRecord::new(
+            0,
+            Utc::now(),
+            "task-q".to_owned(),
+            "/tmp/task-q.log".to_owned(),
+            None,
+        )
+        .queued();
+        let by_zero = TaskFilter {
+            ids: vec!["0".to_owned()],
+            ..TaskFilter::default()
+        };
+        assert!(!by_zero.matches(&queued));
+        let entries = [entry(record), entry(queued)];
+        assert_eq!(by_zero.unmatched_id(&entries), Some("0"));
+        assert_eq!(by_pid.unmatched_id(&entries), Some("7"));
+        assert_eq!(by_task_id.unmatched_id(&entries), None);
+    }
+
+    #[test]
+    fn maps_outcomes_to_exit_code() {
+        let completion = |exit_code: Option<i32>, result: &str| {
+            let record = TaskRecord::new(
+                42,
+                Utc::now(),
+                "task-42".to_owned(),
+                "/tmp/task-42.log".to_owned(),
+                None,
+            )
+            .mark_completed(Some(result.to_owned()), exit_code, Utc::now());
+            TaskCompletion::from_record(record)
+        };
+        let report_of = |completions: Vec<TaskCompletion>| {
+            let mut report = TaskReport::new();
+            for completion in completions {
+                report.add_completion(completion);
+            }
+            report
+        };
+
+        let succeeded = report_of(vec![completion(Some(0), "success")]);
+        assert_eq!(exit_code(&succeeded, false, false), 0);
+        assert_eq!(exit_code(&succeeded, true, false), WAIT_EXIT_TIMED_OUT);
+
+        // 被 SIGKILL（例如 OOM）终止的任务没有退出码
+        let killed = report_of(vec![
+            completion(Some(0), "success"),
+            completion(None, "failed_without_exit_code"),
+        ]);
+        assert_eq!(exit_code(&killed, false, false), WAIT_EXIT_FAILED);
+        assert_eq!(exit_code(&killed, false, true), 0);
+
+        let failed = report_of(vec![completion(Some(2), "failed_with_exit_code_2")]);
+        assert_eq!(exit_code(&failed, false, false), WAIT_EXIT_FAILED);
+
+        let cancelled = TaskCompletion::from_record(
+            TaskRecord::new(
+                7,
+                Utc::now(),
+                "task-7".to_owned(),
+                "/tmp/task-7.log".to_owned(),
+                None,
+            )
+            .with_cleanup_reason(CleanupReason::CancelledByUser.as_str()),
+        );
+        assert!(!cancelled.is_success());
+        assert!(cancelled.is_cancelled());
     }
 
     #[test]
-    fn returns_default_on_invalid_values() {
-        let _lock = ENV_GUARD.lock().unwrap();
-        clear_env();
-        unsafe {
-            env::set_var(WAIT_INTERVAL_ENV, "not-a-number");
-        }
-        assert_eq!(read_interval(), WAIT_INTERVAL_DEFAULT);
-        clear_env();
+    fn formats_duration_per_language() {
+        let duration = chrono::Duration::seconds(3725);
+        assert_eq!(
+            format_human_duration(duration, Lang::En.messages()),
+            "1h 2m 5s"
+        );
+        assert_eq!(
+            format_human_duration(duration, Lang::ZhCn.messages()),
+            "1小时2分5秒"
+        );
     }
 }